@@ -0,0 +1,671 @@
+//! Generates the Jellyfin API bindings from the bundled `openapi.json`.
+//!
+//! Two files are written to `OUT_DIR`:
+//! - `jellyfin_models.rs`: one serde type per schema in `components/schemas`.
+//! - `jellyfin_endpoints.rs`: an `impl JellyfinClient` block with one method
+//!   per operation, plus a `<Operation>Query` struct for its query parameters.
+//!
+//! They are pulled in with `include!` by `src/api/models.rs` and
+//! `src/api/endpoints.rs`, which also hold the small helpers the generated
+//! code relies on.
+
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+const SPEC_PATH: &str = "openapi.json";
+
+/// Variant added to every enum so that values introduced by newer servers
+/// don't fail deserialization of the whole response.
+const FALLBACK_VARIANT: &str = "Unrecognized";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", SPEC_PATH);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let spec = fs::read_to_string(SPEC_PATH).expect("failed to read openapi.json");
+    let spec: Value = serde_json::from_str(&spec).expect("failed to parse openapi.json");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
+
+    let schemas = spec["components"]["schemas"]
+        .as_object()
+        .expect("spec has no components/schemas");
+    let models = Models::new(schemas);
+    let models_src = models.generate();
+    fs::write(out_dir.join("jellyfin_models.rs"), models_src).expect("failed to write models");
+
+    let endpoints_src = generate_endpoints(&spec, &models);
+    fs::write(out_dir.join("jellyfin_endpoints.rs"), endpoints_src).expect("failed to write endpoints");
+}
+
+// ---------------------------------------------------------------------------
+// Naming
+// ---------------------------------------------------------------------------
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// `PlaySessionId` -> `play_session_id`, `IsHD` -> `is_hd`, `H264Level` -> `h264_level`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '-' || c == '.' || c == ' ' || c == '_' {
+            if !out.ends_with('_') && !out.is_empty() {
+                out.push('_');
+            }
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            let boundary = prev.is_ascii_lowercase()
+                || (prev.is_ascii_uppercase() && next_lower)
+                || (prev.is_ascii_digit() && next_lower);
+            if boundary && !out.ends_with('_') {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+fn field_ident(name: &str) -> String {
+    let ident = snake_case(name);
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else {
+        ident
+    }
+}
+
+/// `tvshows` -> `Tvshows`, `v4l2m2m` -> `V4l2m2m`, `application/x-mpegURL` -> `ApplicationXMpegURL`.
+fn variant_ident(value: &str) -> String {
+    let mut out = String::new();
+    for part in value.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            out.push(first.to_ascii_uppercase());
+            out.extend(chars);
+        }
+    }
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, 'V');
+    }
+    out
+}
+
+fn write_doc(out: &mut String, indent: &str, doc: Option<&str>) {
+    let Some(doc) = doc else { return };
+    for line in doc.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        writeln!(out, "{}/// {}", indent, line).unwrap();
+    }
+}
+
+fn ref_name(reference: &str) -> &str {
+    reference.rsplit('/').next().unwrap()
+}
+
+/// Resolves `{"$ref": ...}` and the single-element `{"allOf": [{"$ref": ...}]}`
+/// wrapper the spec uses for nullable references.
+fn schema_ref(schema: &Value) -> Option<&str> {
+    if let Some(r) = schema.get("$ref").and_then(Value::as_str) {
+        return Some(ref_name(r));
+    }
+    match schema.get("allOf").and_then(Value::as_array) {
+        Some(all) if all.len() == 1 => all[0].get("$ref").and_then(Value::as_str).map(ref_name),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Models
+// ---------------------------------------------------------------------------
+
+#[derive(Clone)]
+enum Ty {
+    Bool,
+    I32,
+    I64,
+    F32,
+    F64,
+    String,
+    Json,
+    Named(String),
+    Vec(Box<Ty>),
+    Map(Box<Ty>),
+}
+
+impl Ty {
+    fn render(&self) -> String {
+        match self {
+            Ty::Bool => "bool".into(),
+            Ty::I32 => "i32".into(),
+            Ty::I64 => "i64".into(),
+            Ty::F32 => "f32".into(),
+            Ty::F64 => "f64".into(),
+            Ty::String => "String".into(),
+            Ty::Json => "serde_json::Value".into(),
+            Ty::Named(name) => name.clone(),
+            Ty::Vec(inner) => format!("Vec<{}>", inner.render()),
+            Ty::Map(inner) => format!("HashMap<String, {}>", inner.render()),
+        }
+    }
+
+    fn is_vec(&self) -> bool {
+        matches!(self, Ty::Vec(_))
+    }
+
+    fn is_str(&self) -> bool {
+        matches!(self, Ty::String)
+    }
+}
+
+/// Maps a schema to a Rust type. Inline object schemas are registered as
+/// nested structs named after their parent and property.
+fn map_type(schema: &Value, inline_name: &str, inline: &mut Vec<(String, Value)>) -> Ty {
+    if let Some(name) = schema_ref(schema) {
+        return Ty::Named(name.to_string());
+    }
+
+    let format = schema.get("format").and_then(Value::as_str);
+    match schema.get("type").and_then(Value::as_str) {
+        Some("boolean") => Ty::Bool,
+        Some("integer") => match format {
+            Some("int32") => Ty::I32,
+            _ => Ty::I64,
+        },
+        Some("number") => match format {
+            Some("float") => Ty::F32,
+            _ => Ty::F64,
+        },
+        Some("string") => Ty::String,
+        Some("array") => Ty::Vec(Box::new(map_type(&schema["items"], inline_name, inline))),
+        Some("object") if schema.get("properties").is_some() => {
+            inline.push((inline_name.to_string(), schema.clone()));
+            Ty::Named(inline_name.to_string())
+        }
+        Some("object") => match schema.get("additionalProperties") {
+            Some(ap) if ap.is_object() => Ty::Map(Box::new(map_type(ap, inline_name, inline))),
+            _ => Ty::Json,
+        },
+        _ => Ty::Json,
+    }
+}
+
+struct Field {
+    json_name: String,
+    ident: String,
+    ty: Ty,
+    optional: bool,
+    boxed: bool,
+    doc: Option<String>,
+}
+
+enum Model {
+    Struct {
+        doc: Option<String>,
+        fields: Vec<Field>,
+    },
+    Enum {
+        doc: Option<String>,
+        values: Vec<String>,
+    },
+    Alias {
+        doc: Option<String>,
+    },
+}
+
+struct Models {
+    models: BTreeMap<String, Model>,
+    defaultable: HashSet<String>,
+}
+
+impl Models {
+    fn new(schemas: &Map<String, Value>) -> Self {
+        let mut models = BTreeMap::new();
+        let mut pending: Vec<(String, Value)> = schemas.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+
+        while let Some((name, schema)) = pending.pop() {
+            let doc = schema.get("description").and_then(Value::as_str).map(str::to_string);
+
+            let model = if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+                Model::Enum {
+                    doc,
+                    values: values.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+                }
+            } else if schema.get("oneOf").is_some() || schema.get("anyOf").is_some() {
+                Model::Alias { doc }
+            } else {
+                let mut fields = Vec::new();
+                let mut seen = HashSet::new();
+                let required: HashSet<&str> = schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .map(|r| r.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+
+                if let Some(props) = schema.get("properties").and_then(Value::as_object) {
+                    for (prop, prop_schema) in props {
+                        let ident = field_ident(prop);
+                        assert!(seen.insert(ident.clone()), "duplicate field {} in {}", ident, name);
+
+                        let nullable = prop_schema.get("nullable").and_then(Value::as_bool).unwrap_or(false);
+                        let ty = map_type(prop_schema, &format!("{}{}", name, prop), &mut pending);
+                        fields.push(Field {
+                            json_name: prop.clone(),
+                            ident,
+                            ty,
+                            optional: nullable || !required.contains(prop.as_str()),
+                            boxed: false,
+                            doc: prop_schema.get("description").and_then(Value::as_str).map(str::to_string),
+                        });
+                    }
+                }
+                Model::Struct { doc, fields }
+            };
+            models.insert(name, model);
+        }
+
+        let mut this = Self {
+            models,
+            defaultable: HashSet::new(),
+        };
+        this.box_recursive_fields();
+        this.compute_defaultable();
+        this
+    }
+
+    /// Types a struct stores inline (not behind a `Vec` or map).
+    fn embedded(&self, name: &str) -> Vec<String> {
+        match self.models.get(name) {
+            Some(Model::Struct { fields, .. }) => fields
+                .iter()
+                .filter_map(|f| match &f.ty {
+                    Ty::Named(n) if matches!(self.models.get(n), Some(Model::Struct { .. })) => Some(n.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn reaches(&self, from: &str, target: &str) -> bool {
+        let mut stack = vec![from.to_string()];
+        let mut visited = HashSet::new();
+        while let Some(name) = stack.pop() {
+            if name == target {
+                return true;
+            }
+            if visited.insert(name.clone()) {
+                stack.extend(self.embedded(&name));
+            }
+        }
+        false
+    }
+
+    /// Boxes any inline field whose type can contain the parent again, which
+    /// would otherwise make the struct infinitely sized.
+    fn box_recursive_fields(&mut self) {
+        let mut to_box = Vec::new();
+        for name in self.models.keys() {
+            if let Some(Model::Struct { fields, .. }) = self.models.get(name) {
+                for (i, field) in fields.iter().enumerate() {
+                    if let Ty::Named(target) = &field.ty
+                        && matches!(self.models.get(target), Some(Model::Struct { .. }))
+                        && self.reaches(target, name)
+                    {
+                        to_box.push((name.clone(), i));
+                    }
+                }
+            }
+        }
+        for (name, i) in to_box {
+            if let Some(Model::Struct { fields, .. }) = self.models.get_mut(&name) {
+                fields[i].boxed = true;
+                fields[i].optional = true;
+            }
+        }
+    }
+
+    fn ty_defaultable(&self, ty: &Ty) -> bool {
+        match ty {
+            Ty::Named(name) => self.defaultable.contains(name),
+            _ => true,
+        }
+    }
+
+    fn compute_defaultable(&mut self) {
+        loop {
+            let mut changed = false;
+            for (name, model) in &self.models {
+                if self.defaultable.contains(name) {
+                    continue;
+                }
+                let ok = match model {
+                    Model::Struct { fields, .. } => fields.iter().all(|f| f.optional || self.ty_defaultable(&f.ty)),
+                    Model::Alias { .. } => true,
+                    Model::Enum { .. } => false,
+                };
+                if ok {
+                    self.defaultable.insert(name.clone());
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    fn generate(&self) -> String {
+        let mut out = String::new();
+        out.push_str("// @generated by build.rs from openapi.json. Do not edit.\n\n");
+        out.push_str("use serde::{Deserialize, Serialize};\n");
+        out.push_str("use std::collections::HashMap;\n\n");
+
+        for (name, model) in &self.models {
+            match model {
+                Model::Alias { doc } => {
+                    write_doc(&mut out, "", doc.as_deref());
+                    writeln!(out, "pub type {} = serde_json::Value;\n", name).unwrap();
+                }
+                Model::Enum { doc, values } => {
+                    write_doc(&mut out, "", doc.as_deref());
+                    out.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]\n");
+                    writeln!(out, "pub enum {} {{", name).unwrap();
+                    let mut variants = Vec::new();
+                    for value in values {
+                        let variant = variant_ident(value);
+                        writeln!(out, "    #[serde(rename = {:?})]", value).unwrap();
+                        writeln!(out, "    {},", variant).unwrap();
+                        variants.push((variant, value.clone()));
+                    }
+                    out.push_str("    #[serde(other)]\n");
+                    writeln!(out, "    {},", FALLBACK_VARIANT).unwrap();
+                    out.push_str("}\n\n");
+
+                    writeln!(out, "impl {} {{", name).unwrap();
+                    out.push_str("    pub fn as_str(&self) -> &'static str {\n        match self {\n");
+                    for (variant, value) in &variants {
+                        writeln!(out, "            Self::{} => {:?},", variant, value).unwrap();
+                    }
+                    writeln!(out, "            Self::{} => \"{}\",", FALLBACK_VARIANT, FALLBACK_VARIANT).unwrap();
+                    out.push_str("        }\n    }\n}\n\n");
+                }
+                Model::Struct { doc, fields } => {
+                    write_doc(&mut out, "", doc.as_deref());
+                    let default = if self.defaultable.contains(name) { ", Default" } else { "" };
+                    writeln!(out, "#[derive(Debug, Clone, PartialEq{}, Serialize, Deserialize)]", default).unwrap();
+                    writeln!(out, "pub struct {} {{", name).unwrap();
+                    for field in fields {
+                        write_doc(&mut out, "    ", field.doc.as_deref());
+                        let mut ty = field.ty.render();
+                        if field.boxed {
+                            ty = format!("Box<{}>", ty);
+                        }
+                        if field.optional {
+                            writeln!(
+                                out,
+                                "    #[serde(rename = {:?}, default, skip_serializing_if = \"Option::is_none\")]",
+                                field.json_name
+                            )
+                            .unwrap();
+                            writeln!(out, "    pub {}: Option<{}>,", field.ident, ty).unwrap();
+                        } else {
+                            writeln!(out, "    #[serde(rename = {:?})]", field.json_name).unwrap();
+                            writeln!(out, "    pub {}: {},", field.ident, ty).unwrap();
+                        }
+                    }
+                    out.push_str("}\n\n");
+                }
+            }
+        }
+        out
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Endpoints
+// ---------------------------------------------------------------------------
+
+struct Param {
+    name: String,
+    ident: String,
+    ty: Ty,
+    required: bool,
+    doc: Option<String>,
+}
+
+fn generate_endpoints(spec: &Value, models: &Models) -> String {
+    let mut out = String::new();
+    out.push_str("// @generated by build.rs from openapi.json. Do not edit.\n\n");
+
+    let mut queries = String::new();
+    let mut methods = String::new();
+    let mut seen_ops = BTreeSet::new();
+
+    let paths = spec["paths"].as_object().expect("spec has no paths");
+    for (path, item) in paths {
+        let Some(item) = item.as_object() else { continue };
+        for (http_method, op) in item {
+            let Some(op_id) = op.get("operationId").and_then(Value::as_str) else {
+                continue;
+            };
+            assert!(seen_ops.insert(op_id.to_string()), "duplicate operation {}", op_id);
+            generate_operation(&mut queries, &mut methods, models, path, http_method, op_id, op);
+        }
+    }
+
+    out.push_str(&queries);
+    out.push_str("impl JellyfinClient {\n");
+    out.push_str(&methods);
+    out.push_str("}\n");
+    out
+}
+
+fn generate_operation(
+    queries: &mut String,
+    methods: &mut String,
+    models: &Models,
+    path: &str,
+    http_method: &str,
+    op_id: &str,
+    op: &Value,
+) {
+    let method_ident = field_ident(op_id);
+    let query_type = format!("{}Query", op_id);
+    assert!(!models.models.contains_key(&query_type), "{} clashes with a model", query_type);
+
+    let mut no_inline = Vec::new();
+    let mut path_params = Vec::new();
+    let mut query_params = Vec::new();
+    for param in op.get("parameters").and_then(Value::as_array).into_iter().flatten() {
+        let name = param["name"].as_str().unwrap().to_string();
+        let ty = map_type(&param["schema"], "", &mut no_inline);
+        // Object-valued query parameters (`streamOptions`) are a
+        // dictionary binder that doesn't survive urlencoding; leave them out.
+        if matches!(ty, Ty::Map(_) | Ty::Json) {
+            continue;
+        }
+        let p = Param {
+            ident: field_ident(&name),
+            name,
+            ty,
+            required: param.get("required").and_then(Value::as_bool).unwrap_or(false),
+            doc: param.get("description").and_then(Value::as_str).map(str::to_string),
+        };
+        match param["in"].as_str() {
+            Some("path") => path_params.push(p),
+            Some("query") => query_params.push(p),
+            _ => {}
+        }
+    }
+    assert!(no_inline.is_empty(), "inline object parameter in {}", op_id);
+
+    // Query struct
+    if !query_params.is_empty() {
+        let defaultable = query_params.iter().all(|p| !p.required || models.ty_defaultable(&p.ty));
+        writeln!(queries, "/// Query parameters for [`JellyfinClient::{}`].", method_ident).unwrap();
+        let default = if defaultable { ", Default" } else { "" };
+        writeln!(queries, "#[derive(Debug, Clone, PartialEq{}, Serialize)]", default).unwrap();
+        writeln!(queries, "pub struct {} {{", query_type).unwrap();
+        let mut seen = HashSet::new();
+        for p in &query_params {
+            assert!(seen.insert(p.ident.clone()), "duplicate query param {} in {}", p.ident, op_id);
+            write_doc(queries, "    ", p.doc.as_deref());
+            let ty = p.ty.render();
+            match (p.required, p.ty.is_vec()) {
+                (true, false) => {
+                    writeln!(queries, "    #[serde(rename = {:?})]", p.name).unwrap();
+                    writeln!(queries, "    pub {}: {},", p.ident, ty).unwrap();
+                }
+                (true, true) => {
+                    writeln!(queries, "    #[serde(rename = {:?}, serialize_with = \"serialize_csv\")]", p.name).unwrap();
+                    writeln!(queries, "    pub {}: {},", p.ident, ty).unwrap();
+                }
+                (false, false) => {
+                    writeln!(
+                        queries,
+                        "    #[serde(rename = {:?}, skip_serializing_if = \"Option::is_none\")]",
+                        p.name
+                    )
+                    .unwrap();
+                    writeln!(queries, "    pub {}: Option<{}>,", p.ident, ty).unwrap();
+                }
+                (false, true) => {
+                    writeln!(
+                        queries,
+                        "    #[serde(rename = {:?}, skip_serializing_if = \"Option::is_none\", serialize_with = \"serialize_csv_opt\")]",
+                        p.name
+                    )
+                    .unwrap();
+                    writeln!(queries, "    pub {}: Option<{}>,", p.ident, ty).unwrap();
+                }
+            }
+        }
+        queries.push_str("}\n\n");
+    }
+
+    // Body
+    let mut body_arg = None;
+    if let Some(content) = op.get("requestBody").and_then(|b| b.get("content")).and_then(Value::as_object) {
+        if let Some(schema) = content.get("application/json").and_then(|c| c.get("schema")) {
+            let ty = map_type(schema, "", &mut no_inline);
+            body_arg = Some((format!("body: &{}", ty.render()), ".json(body)".to_string()));
+        } else if let Some(content_type) = content.keys().next() {
+            body_arg = Some((
+                "body: impl Into<reqwest::Body>".to_string(),
+                format!(".header(\"Content-Type\", {:?}).body(body)", content_type),
+            ));
+        }
+    }
+
+    // Response
+    let responses = op.get("responses").and_then(Value::as_object);
+    let success = responses.and_then(|r| r.iter().find(|(code, _)| code.starts_with('2')).map(|(_, v)| v));
+    let content = success.and_then(|s| s.get("content")).and_then(Value::as_object);
+    enum Returns {
+        Unit,
+        Json(Ty),
+        Bytes,
+    }
+    let returns = match content {
+        _ if http_method == "head" => Returns::Unit,
+        None => Returns::Unit,
+        Some(content) => match content.get("application/json").and_then(|c| c.get("schema")) {
+            Some(schema) => Returns::Json(map_type(schema, "", &mut no_inline)),
+            None => Returns::Bytes,
+        },
+    };
+
+    // Signature
+    let mut args = vec!["&self".to_string()];
+    for p in &path_params {
+        if p.ty.is_str() {
+            args.push(format!("{}: &str", p.ident));
+        } else {
+            args.push(format!("{}: {}", p.ident, p.ty.render()));
+        }
+    }
+    if !query_params.is_empty() {
+        args.push(format!("query: &{}", query_type));
+    }
+    let url_args = args.clone();
+    if let Some((arg, _)) = &body_arg {
+        args.push(arg.clone());
+    }
+
+    // Path expression
+    let mut path_expr = path.trim_start_matches('/').to_string();
+    let mut fmt_args = Vec::new();
+    for p in &path_params {
+        path_expr = path_expr.replace(&format!("{{{}}}", p.name), "{}");
+        if p.ty.is_str() {
+            fmt_args.push(format!("urlencoding::encode({})", p.ident));
+        } else {
+            fmt_args.push(format!("path_param(&{})", p.ident));
+        }
+    }
+    let path_expr = if fmt_args.is_empty() {
+        format!("{:?}", path_expr)
+    } else {
+        format!("&format!({:?}, {})", path_expr, fmt_args.join(", "))
+    };
+
+    let doc_summary = op.get("summary").and_then(Value::as_str);
+    let http_upper = http_method.to_uppercase();
+    let method_const = match http_method {
+        "get" => "GET",
+        "post" => "POST",
+        "delete" => "DELETE",
+        "head" => "HEAD",
+        "put" => "PUT",
+        other => panic!("unsupported method {} in {}", other, op_id),
+    };
+
+    let mut request = format!("self.request(Method::{}, {})?", method_const, path_expr);
+    if !query_params.is_empty() {
+        request.push_str(".query(query)");
+    }
+    if let Some((_, call)) = &body_arg {
+        request.push_str(call);
+    }
+
+    write_doc(methods, "    ", doc_summary);
+    if doc_summary.is_some() {
+        methods.push_str("    ///\n");
+    }
+    writeln!(methods, "    /// `{} {}`", http_upper, path).unwrap();
+    if op.get("deprecated").and_then(Value::as_bool).unwrap_or(false) {
+        methods.push_str("    #[deprecated]\n");
+    }
+    let (ret, body) = match &returns {
+        Returns::Unit => ("()".to_string(), format!("        self.send({}).await.map(|_| ())", request)),
+        Returns::Json(ty) => (ty.render(), format!("        self.send_json({}).await", request)),
+        Returns::Bytes => ("Vec<u8>".to_string(), format!("        self.send_bytes({}).await", request)),
+    };
+    writeln!(methods, "    pub async fn {}({}) -> Result<{}> {{", method_ident, args.join(", "), ret).unwrap();
+    writeln!(methods, "{}", body).unwrap();
+    methods.push_str("    }\n\n");
+
+    // Media endpoints are usually handed to a player or image loader rather
+    // than fetched here, so they also get a URL builder.
+    if matches!(returns, Returns::Bytes) && http_method == "get" {
+        writeln!(methods, "    /// URL for [`Self::{}`], including query parameters.", method_ident).unwrap();
+        writeln!(methods, "    pub fn {}_url({}) -> Result<Url> {{", method_ident, url_args.join(", ")).unwrap();
+        let mut request = format!("self.request(Method::GET, {})?", path_expr);
+        if !query_params.is_empty() {
+            request.push_str(".query(query)");
+        }
+        writeln!(methods, "        self.built_url({})", request).unwrap();
+        methods.push_str("    }\n\n");
+    }
+}
@@ -0,0 +1,199 @@
+use super::client::{DeviceInfo, JellyfinClient};
+use super::error::{ApiError, Result};
+use super::models::PublicSystemInfo;
+use reqwest::Url;
+
+pub const DEFAULT_HTTP_PORT: u16 = 8096;
+pub const DEFAULT_HTTPS_PORT: u16 = 8920;
+
+/// Web UI paths people tend to copy from the browser along with the server address.
+const WEB_UI_SUFFIXES: &[&str] = &["/web/index.html", "/web/", "/web"];
+
+/// A server that answered `/System/Info/Public`, with the base URL to store.
+#[derive(Debug, Clone)]
+pub struct ResolvedServer {
+    pub base_url: Url,
+    pub info: PublicSystemInfo,
+}
+
+impl ResolvedServer {
+    /// The base URL in the form kept in `Config`, without a trailing slash.
+    pub fn url(&self) -> String {
+        self.base_url.as_str().trim_end_matches('/').to_string()
+    }
+}
+
+/// Expands what the user typed into the base URLs worth trying, most likely first.
+///
+/// Without a scheme both https and http are tried. Without a port, https tries
+/// 443 before Jellyfin's 8920 while http tries 8096 before 80. A web UI path
+/// and trailing slashes are dropped, keeping any sub-path the server is at.
+pub fn candidate_urls(input: &str) -> Result<Vec<Url>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(ApiError::InvalidUrl("no address given".to_string()));
+    }
+
+    let has_scheme = input.contains("://");
+    let with_scheme = if has_scheme { input.to_string() } else { format!("http://{}", input) };
+    let mut url = Url::parse(&with_scheme).map_err(|e| ApiError::InvalidUrl(e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(ApiError::InvalidUrl(input.to_string()));
+    }
+
+    url.set_query(None);
+    url.set_fragment(None);
+    let path = strip_web_ui_suffix(url.path()).trim_end_matches('/').to_string();
+    url.set_path(&path);
+
+    let schemes: Vec<String> = if has_scheme {
+        vec![url.scheme().to_string()]
+    } else {
+        vec!["https".to_string(), "http".to_string()]
+    };
+
+    let mut candidates = Vec::new();
+    for scheme in schemes {
+        let mut candidate = url.clone();
+        // Switching between http and https is always allowed.
+        let _ = candidate.set_scheme(&scheme);
+
+        if url.port().is_some() {
+            candidates.push(candidate);
+            continue;
+        }
+
+        let default_port = if scheme == "https" { DEFAULT_HTTPS_PORT } else { DEFAULT_HTTP_PORT };
+        let mut with_port = candidate.clone();
+        let _ = with_port.set_port(Some(default_port));
+
+        // Plain http on port 80 is the least likely setup; try Jellyfin's port first.
+        if scheme == "https" {
+            candidates.push(candidate);
+            candidates.push(with_port);
+        } else {
+            candidates.push(with_port);
+            candidates.push(candidate);
+        }
+    }
+
+    Ok(candidates)
+}
+
+fn strip_web_ui_suffix(path: &str) -> &str {
+    let lower = path.to_ascii_lowercase();
+    for suffix in WEB_UI_SUFFIXES {
+        if lower.ends_with(suffix) {
+            return &path[..path.len() - suffix.len()];
+        }
+    }
+    path
+}
+
+/// Finds the Jellyfin server behind a user-typed address.
+///
+/// All candidates are probed concurrently but the result follows candidate
+/// order, so an https answer wins over http even if http was faster. When the
+/// server reports a `LocalAddress` that is reachable and belongs to the same
+/// server, that address is preferred as the canonical one.
+pub async fn resolve_server(input: &str, http: &reqwest::Client, device: &DeviceInfo) -> Result<ResolvedServer> {
+    let candidates = candidate_urls(input)?;
+
+    let mut probes = Vec::new();
+    for candidate in candidates {
+        let client = JellyfinClient::new(http.clone(), candidate.as_str(), device.clone())?;
+        probes.push(tokio::spawn(async move { client.probe().await }));
+    }
+
+    let mut resolved = None;
+    let mut errors = Vec::new();
+    for i in 0..probes.len() {
+        match (&mut probes[i]).await {
+            Ok(Ok((info, base_url))) if info.id.is_some() => {
+                resolved = Some(ResolvedServer { base_url, info });
+                // Dropping a JoinHandle detaches the task, so stop the rest explicitly.
+                probes[i + 1..].iter().for_each(|p| p.abort());
+                break;
+            }
+            // Something answered with JSON, but not with a server id.
+            Ok(Ok(_)) => errors.push(ApiError::NotFound),
+            Ok(Err(e)) => errors.push(e),
+            Err(_) => {}
+        }
+    }
+
+    let Some(mut resolved) = resolved else {
+        // An answer from the wrong kind of server says more than a refused connection.
+        let error = match errors.iter().position(|e| !e.is_network()) {
+            Some(i) => errors.swap_remove(i),
+            None if !errors.is_empty() => errors.swap_remove(0),
+            None => ApiError::NotFound,
+        };
+        return Err(error);
+    };
+
+    if let Some(local) = resolved.info.local_address.clone()
+        && let Ok(local_client) = JellyfinClient::new(http.clone(), &local, device.clone())
+        && local_client.base_url() != &resolved.base_url
+        && let Ok((local_info, local_base)) = local_client.probe().await
+        && local_info.id == resolved.info.id
+    {
+        resolved.base_url = local_base;
+    }
+
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_addresses() {
+        let cases: &[(&str, &[&str])] = &[
+            (
+                "192.168.1.5",
+                &["https://192.168.1.5/", "https://192.168.1.5:8920/", "http://192.168.1.5:8096/", "http://192.168.1.5/"],
+            ),
+            (
+                " jellyfin.lan ",
+                &["https://jellyfin.lan/", "https://jellyfin.lan:8920/", "http://jellyfin.lan:8096/", "http://jellyfin.lan/"],
+            ),
+            ("jellyfin.lan:9000", &["https://jellyfin.lan:9000/", "http://jellyfin.lan:9000/"]),
+            ("[::1]:8096", &["https://[::1]:8096/", "http://[::1]:8096/"]),
+            ("http://jellyfin.lan", &["http://jellyfin.lan:8096/", "http://jellyfin.lan/"]),
+            ("https://media.example.com", &["https://media.example.com/", "https://media.example.com:8920/"]),
+            ("https://media.example.com:8443/", &["https://media.example.com:8443/"]),
+            // Copied from the browser's address bar.
+            ("http://192.168.1.5:8096/web/index.html#!/home.html", &["http://192.168.1.5:8096/"]),
+            ("http://192.168.1.5:8096/web/", &["http://192.168.1.5:8096/"]),
+            ("https://example.com/jellyfin/web/index.html", &["https://example.com/jellyfin", "https://example.com:8920/jellyfin"]),
+            ("https://example.com/jellyfin/WEB?x=1", &["https://example.com/jellyfin", "https://example.com:8920/jellyfin"]),
+            ("https://example.com/jellyfin/", &["https://example.com/jellyfin", "https://example.com:8920/jellyfin"]),
+        ];
+        for (input, expected) in cases {
+            let urls: Vec<String> = candidate_urls(input).unwrap().iter().map(Url::to_string).collect();
+            assert_eq!(urls, *expected, "for {:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_addresses() {
+        for input in ["", "   ", "ftp://example.com", "http://", "file:///srv/media", "exa mple.com", "host:port"] {
+            assert!(
+                matches!(candidate_urls(input), Err(ApiError::InvalidUrl(_))),
+                "accepted {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn stored_url_has_no_trailing_slash() {
+        let server = ResolvedServer {
+            base_url: Url::parse("https://example.com/jellyfin/").unwrap(),
+            info: PublicSystemInfo::default(),
+        };
+        assert_eq!(server.url(), "https://example.com/jellyfin");
+    }
+}
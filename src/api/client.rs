@@ -0,0 +1,173 @@
+use super::error::{ApiError, Result};
+use super::models::PublicSystemInfo;
+use reqwest::{Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(8);
+const PUBLIC_INFO_PATH: &str = "System/Info/Public";
+
+/// Identifies this client to the server in the `Authorization` header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    pub client: String,
+    pub device_name: String,
+    pub device_id: String,
+    pub version: String,
+}
+
+impl DeviceInfo {
+    /// Describes this machine using a device id that must stay stable across
+    /// runs; a fresh id makes the server register a new device every login.
+    pub fn new(device_id: impl Into<String>) -> Self {
+        let device_name = gethostname::gethostname()
+            .to_string_lossy()
+            .trim()
+            .to_string();
+
+        Self {
+            client: "Crabfin".to_string(),
+            device_name: if device_name.is_empty() { "Crabfin Client".to_string() } else { device_name },
+            device_id: device_id.into(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// A connection to a single Jellyfin server.
+///
+/// Cloning is cheap: clones share the underlying connection pool.
+#[derive(Debug, Clone)]
+pub struct JellyfinClient {
+    http: reqwest::Client,
+    base_url: Url,
+    device: DeviceInfo,
+    access_token: Option<String>,
+}
+
+impl JellyfinClient {
+    /// Builds the HTTP client, and with it the connection pool, that every
+    /// `JellyfinClient` of this device shares. Build it once and pass clones
+    /// to [`Self::new`].
+    pub fn http_client(device: &DeviceInfo) -> Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .user_agent(format!("{}/{}", device.client, device.version))
+            .build()
+            .map_err(ApiError::Request)
+    }
+
+    pub fn new(http: reqwest::Client, base_url: &str, device: DeviceInfo) -> Result<Self> {
+        Ok(Self {
+            http,
+            base_url: Self::parse_base_url(base_url)?,
+            device,
+            access_token: None,
+        })
+    }
+
+    pub fn with_access_token(mut self, access_token: impl Into<String>) -> Self {
+        self.access_token = Some(access_token.into());
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    pub fn device(&self) -> &DeviceInfo {
+        &self.device
+    }
+
+    /// Parses a server address, making sure the path ends with a slash so
+    /// that relative endpoint paths are appended rather than substituted.
+    fn parse_base_url(url: &str) -> Result<Url> {
+        let mut url = Url::parse(url.trim()).map_err(|e| ApiError::InvalidUrl(e.to_string()))?;
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+        url.set_query(None);
+        url.set_fragment(None);
+        Ok(url)
+    }
+
+    /// Resolves an endpoint path such as `System/Info/Public` against the base URL.
+    pub fn endpoint_url(&self, path: &str) -> Result<Url> {
+        self.base_url
+            .join(path.trim_start_matches('/'))
+            .map_err(|e| ApiError::InvalidUrl(e.to_string()))
+    }
+
+    pub(super) fn request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        let url = self.endpoint_url(path)?;
+        Ok(self
+            .http
+            .request(method, url)
+            .header("Authorization", self.auth_header()))
+    }
+
+    /// The `Authorization` value sent with every request. Exposed for
+    /// requests made outside this client, such as by the video player.
+    pub fn auth_header(&self) -> String {
+        // The server URL-decodes each value, which keeps hostnames with
+        // quotes or non-ASCII characters from breaking the header.
+        let mut auth = format!(
+            r#"MediaBrowser Client="{}", Device="{}", DeviceId="{}", Version="{}""#,
+            urlencoding::encode(&self.device.client),
+            urlencoding::encode(&self.device.device_name),
+            urlencoding::encode(&self.device.device_id),
+            urlencoding::encode(&self.device.version)
+        );
+
+        if let Some(token) = &self.access_token {
+            auth.push_str(&format!(r#", Token="{}""#, token));
+        }
+
+        auth
+    }
+
+    /// Sends a request and maps a non-2xx status to the matching [`ApiError`].
+    pub(super) async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let resp = request.send().await?;
+        match ApiError::from_status(resp.status()) {
+            Some(e) => Err(e),
+            None => Ok(resp),
+        }
+    }
+
+    /// Sends a request and decodes the JSON body.
+    ///
+    /// The body is decoded with serde_json directly rather than
+    /// `Response::json` so that schema mismatches surface as
+    /// [`ApiError::Decode`] instead of a generic transport error.
+    pub(super) async fn send_json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let bytes = self.send(request).await?.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    pub(super) async fn send_bytes(&self, request: RequestBuilder) -> Result<Vec<u8>> {
+        Ok(self.send(request).await?.bytes().await?.to_vec())
+    }
+
+    /// Resolves a request to its final URL without sending it.
+    pub(super) fn built_url(&self, request: RequestBuilder) -> Result<Url> {
+        Ok(request.build()?.url().clone())
+    }
+
+    /// Fetches the public server info along with the base URL the request
+    /// ended up at, which differs from [`Self::base_url`] after a redirect.
+    pub async fn probe(&self) -> Result<(PublicSystemInfo, Url)> {
+        let request = self.request(Method::GET, PUBLIC_INFO_PATH)?.timeout(PROBE_TIMEOUT);
+        let resp = self.send(request).await?;
+
+        let mut base_url = resp.url().clone();
+        let base_path = base_url.path().strip_suffix(PUBLIC_INFO_PATH).unwrap_or("/").to_string();
+        base_url.set_path(&base_path);
+        base_url.set_query(None);
+
+        let bytes = resp.bytes().await?;
+        Ok((serde_json::from_slice(&bytes)?, base_url))
+    }
+}
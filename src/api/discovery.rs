@@ -0,0 +1,111 @@
+use serde::Deserialize;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+/// Port Jellyfin listens on for client discovery broadcasts.
+pub const DISCOVERY_PORT: u16 = 7359;
+
+const DISCOVERY_MESSAGE: &[u8] = b"Who is JellyfinServer?";
+
+/// A server that answered the discovery broadcast.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DiscoveredServer {
+    pub id: String,
+    pub name: String,
+    /// Base URL the server advertises, e.g. `http://192.168.1.5:8096`.
+    pub address: String,
+    #[serde(default)]
+    pub endpoint_address: Option<String>,
+}
+
+/// Broadcasts on the local network and collects the servers that reply within `timeout`.
+pub async fn discover_servers(timeout: Duration) -> io::Result<Vec<DiscoveredServer>> {
+    discover_servers_at(SocketAddr::from((Ipv4Addr::BROADCAST, DISCOVERY_PORT)), timeout).await
+}
+
+/// Sends the discovery probe to `target` and collects replies until `timeout`.
+///
+/// Replies that aren't valid server announcements are ignored, and a server
+/// answering on several interfaces is only listed once. Failing to send
+/// the probe is an error; one while listening ends the search with the
+/// servers found so far.
+pub async fn discover_servers_at(target: SocketAddr, timeout: Duration) -> io::Result<Vec<DiscoveredServer>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_broadcast(true)?;
+    socket.send_to(DISCOVERY_MESSAGE, target).await?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 4096];
+    let mut servers: Vec<DiscoveredServer> = Vec::new();
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+
+        match tokio::time::timeout(remaining, socket.recv_from(&mut buf)).await {
+            Ok(Ok((len, _))) => {
+                let Ok(server) = serde_json::from_slice::<DiscoveredServer>(&buf[..len]) else {
+                    continue;
+                };
+                if !servers.iter().any(|s| s.id == server.id) {
+                    servers.push(server);
+                }
+            }
+            // Windows reports an ICMP port unreachable from a host without a
+            // server as a reset on the next receive; other hosts may still answer.
+            Ok(Err(e)) if matches!(e.kind(), io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionRefused) => {
+                tracing::debug!("Ignoring discovery error: {}", e);
+            }
+            // Whatever went wrong, the servers found so far are still there.
+            Ok(Err(e)) => {
+                tracing::warn!("Server discovery stopped early: {}", e);
+                break;
+            }
+            Err(_) => break,
+        }
+    }
+
+    Ok(servers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A reply as sent by Jellyfin 10.9.
+    const REPLY: &[u8] =
+        br#"{"Address":"http://192.168.1.5:8096","Id":"3c0a7f0e1b5a4c7e9d2f8b6a1e4c3d2b","Name":"Living Room","EndpointAddress":null}"#;
+
+    #[tokio::test]
+    async fn lists_servers_answering_the_probe() {
+        let responder = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let target = responder.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, from) = responder.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], DISCOVERY_MESSAGE);
+            // Junk is skipped and a second answer from the same server is
+            // only listed once.
+            responder.send_to(b"not json", from).await.unwrap();
+            responder.send_to(REPLY, from).await.unwrap();
+            responder.send_to(REPLY, from).await.unwrap();
+        });
+
+        let servers = discover_servers_at(target, Duration::from_millis(500)).await.unwrap();
+        assert_eq!(
+            servers,
+            vec![DiscoveredServer {
+                id: "3c0a7f0e1b5a4c7e9d2f8b6a1e4c3d2b".to_string(),
+                name: "Living Room".to_string(),
+                address: "http://192.168.1.5:8096".to_string(),
+                endpoint_address: None,
+            }]
+        );
+    }
+}
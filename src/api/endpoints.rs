@@ -0,0 +1,45 @@
+//! One [`JellyfinClient`] method per operation in the Jellyfin OpenAPI spec.
+//!
+//! Generated by `build.rs`. Path parameters are plain arguments, query
+//! parameters are grouped in a `<Operation>Query` struct and JSON bodies are
+//! passed by reference.
+
+use super::client::JellyfinClient;
+use super::error::Result;
+use super::models::*;
+use reqwest::{Method, Url};
+use serde::{Serialize, Serializer};
+
+include!(concat!(env!("OUT_DIR"), "/jellyfin_endpoints.rs"));
+
+/// Renders a value the way the server expects it in a path or query string:
+/// strings and enums unquoted, numbers and booleans as literals.
+fn param_string<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(other) => other.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+fn path_param<T: Serialize>(value: &T) -> String {
+    urlencoding::encode(&param_string(value)).into_owned()
+}
+
+/// Array query parameters are sent comma-delimited, which the server accepts
+/// for every list parameter.
+#[allow(clippy::ptr_arg)]
+fn serialize_csv<S: Serializer, T: Serialize>(values: &Vec<T>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    let joined = values.iter().map(param_string).collect::<Vec<_>>().join(",");
+    serializer.serialize_str(&joined)
+}
+
+fn serialize_csv_opt<S: Serializer, T: Serialize>(
+    values: &Option<Vec<T>>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match values {
+        Some(values) => serialize_csv(values, serializer),
+        None => serializer.serialize_none(),
+    }
+}
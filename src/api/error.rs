@@ -0,0 +1,115 @@
+use reqwest::StatusCode;
+use std::error::Error as StdError;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, ApiError>;
+
+/// Failures talking to a Jellyfin server.
+///
+/// The variants are coarse on purpose: each one maps to something the user
+/// can act on (fix the address, check the password, try again later).
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Invalid server address: {0}")]
+    InvalidUrl(String),
+
+    #[error("Could not connect to the server")]
+    Connect(#[source] reqwest::Error),
+
+    #[error("Secure connection to the server failed")]
+    Tls(#[source] reqwest::Error),
+
+    #[error("The server did not respond in time")]
+    Timeout(#[source] reqwest::Error),
+
+    #[error("Invalid credentials or expired session")]
+    Unauthorized,
+
+    #[error("Access denied")]
+    Forbidden,
+
+    #[error("Not found")]
+    NotFound,
+
+    #[error("Server error ({0})")]
+    Server(StatusCode),
+
+    #[error("Unexpected response ({0})")]
+    Status(StatusCode),
+
+    #[error("Unexpected response format")]
+    Decode(#[source] serde_json::Error),
+
+    #[error("Network error")]
+    Request(#[source] reqwest::Error),
+}
+
+impl ApiError {
+    /// Maps an unsuccessful HTTP status to an error, or `None` for 2xx.
+    pub fn from_status(status: StatusCode) -> Option<Self> {
+        if status.is_success() {
+            return None;
+        }
+
+        Some(match status {
+            StatusCode::UNAUTHORIZED => ApiError::Unauthorized,
+            StatusCode::FORBIDDEN => ApiError::Forbidden,
+            StatusCode::NOT_FOUND => ApiError::NotFound,
+            status if status.is_server_error() => ApiError::Server(status),
+            status => ApiError::Status(status),
+        })
+    }
+
+    /// Whether the request never reached the server, so retrying later may help.
+    pub fn is_network(&self) -> bool {
+        matches!(
+            self,
+            ApiError::Connect(_) | ApiError::Tls(_) | ApiError::Timeout(_) | ApiError::Request(_)
+        )
+    }
+
+    /// Whether the same request may succeed later: it never reached the
+    /// server, or the server, or a proxy in front of it, failed rather than
+    /// turning the request down.
+    pub fn is_temporary(&self) -> bool {
+        self.is_network() || matches!(self, ApiError::Server(_))
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ApiError::Timeout(e)
+        } else if is_tls_error(&e) {
+            // Checked before `is_connect` since handshake failures are also
+            // reported as connect errors.
+            ApiError::Tls(e)
+        } else if e.is_connect() {
+            ApiError::Connect(e)
+        } else if let Some(status) = e.status().and_then(ApiError::from_status) {
+            status
+        } else {
+            ApiError::Request(e)
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Decode(e)
+    }
+}
+
+/// reqwest doesn't expose TLS failures as a kind, so look for rustls'
+/// wording anywhere in the source chain.
+fn is_tls_error(e: &reqwest::Error) -> bool {
+    let mut source = e.source();
+    while let Some(err) = source {
+        let message = err.to_string().to_lowercase();
+        if message.contains("certificate") || message.contains("tls") || message.contains("handshake") {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
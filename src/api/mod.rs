@@ -0,0 +1,15 @@
+mod address;
+mod client;
+mod discovery;
+#[allow(dead_code, clippy::all)]
+mod endpoints;
+mod error;
+#[allow(dead_code, clippy::all)]
+mod models;
+
+pub use address::{candidate_urls, resolve_server, ResolvedServer};
+pub use client::{DeviceInfo, JellyfinClient};
+pub use discovery::{discover_servers, discover_servers_at, DiscoveredServer, DISCOVERY_PORT};
+pub use endpoints::*;
+pub use error::{ApiError, Result};
+pub use models::*;
@@ -0,0 +1,5 @@
+//! Serde models for every schema in the Jellyfin OpenAPI spec.
+//!
+//! Generated by `build.rs`; see there for the type mapping rules.
+
+include!(concat!(env!("OUT_DIR"), "/jellyfin_models.rs"));
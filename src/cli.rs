@@ -0,0 +1,42 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+/// A native Jellyfin client.
+///
+/// Every option can also be set through the environment variable shown next
+/// to it, which is handier for kiosks and test scripts.
+#[derive(Debug, Clone, Parser)]
+#[command(name = "crabfin", version, about)]
+pub struct Args {
+    /// Directory holding config.json. Instances with different directories also keep their sign-ins apart
+    #[arg(long, env = "CRABFIN_CONFIG_DIR", value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Directory for cached images and other downloaded data
+    #[arg(long, env = "CRABFIN_CACHE_DIR", value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Log filter, a level like `debug` or directives like `crabfin=debug,reqwest=warn`
+    #[arg(long, env = "CRABFIN_LOG", value_name = "FILTER", default_value = "info")]
+    pub log_level: String,
+
+    /// Server to open on start. Added first if it isn't in the config yet
+    #[arg(long, env = "CRABFIN_SERVER", value_name = "URL")]
+    pub server: Option<String>,
+
+    /// Item to open once signed in
+    #[arg(long, env = "CRABFIN_ITEM", value_name = "ID")]
+    pub item: Option<String>,
+}
+
+impl Args {
+    /// Installs the global log subscriber. An invalid filter falls back to
+    /// `info` rather than stopping the app from starting.
+    pub fn init_logging(&self) {
+        let filter = tracing_subscriber::EnvFilter::try_new(&self.log_level).unwrap_or_else(|e| {
+            eprintln!("Ignoring invalid log filter {:?}: {}", self.log_level, e);
+            tracing_subscriber::EnvFilter::new("info")
+        });
+        tracing_subscriber::fmt().with_env_filter(filter).init();
+    }
+}
@@ -0,0 +1,80 @@
+use crate::api::{GetUserImageQuery, JellyfinClient};
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::*;
+use std::sync::Arc;
+
+/// A round user picture, falling back to the name's first letter while the
+/// picture is loading or when the user has none.
+#[derive(IntoElement)]
+pub struct Avatar {
+    name: SharedString,
+    image: Option<Arc<Image>>,
+    size: Pixels,
+}
+
+impl Avatar {
+    pub fn new(name: impl Into<SharedString>, image: Option<Arc<Image>>) -> Self {
+        Self {
+            name: name.into(),
+            image,
+            size: px(64.),
+        }
+    }
+
+    pub fn with_size(mut self, size: Pixels) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl RenderOnce for Avatar {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        let initial = self
+            .name
+            .chars()
+            .next()
+            .map(|c| c.to_uppercase().to_string())
+            .unwrap_or_default();
+
+        div()
+            .size(self.size)
+            .flex_none()
+            .flex()
+            .items_center()
+            .justify_center()
+            .rounded_full()
+            .overflow_hidden()
+            .bg(theme.muted)
+            .text_color(theme.muted_foreground)
+            .map(|this| match self.image {
+                Some(image) => this.child(img(image).size_full().object_fit(ObjectFit::Cover)),
+                None => this.text_size(self.size * 0.4).font_bold().child(initial),
+            })
+    }
+}
+
+/// Wraps downloaded image bytes for `img()`. The API helpers don't keep the
+/// response's content type, so the format is read from the file signature.
+fn image_from_bytes(bytes: Vec<u8>) -> Option<Arc<Image>> {
+    let format = match bytes.as_slice() {
+        [0x89, b'P', b'N', b'G', ..] => ImageFormat::Png,
+        [0xFF, 0xD8, 0xFF, ..] => ImageFormat::Jpeg,
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => ImageFormat::Webp,
+        [b'G', b'I', b'F', b'8', ..] => ImageFormat::Gif,
+        [b'B', b'M', ..] => ImageFormat::Bmp,
+        _ => return None,
+    };
+    Some(Arc::new(Image::from_bytes(format, bytes)))
+}
+
+/// Downloads a user's primary image. `None` when they haven't set one.
+pub async fn fetch_user_avatar(client: &JellyfinClient, user_id: &str, tag: Option<String>) -> Option<Arc<Image>> {
+    let query = GetUserImageQuery {
+        user_id: Some(user_id.to_string()),
+        tag,
+        ..Default::default()
+    };
+    client.get_user_image(&query).await.ok().and_then(image_from_bytes)
+}
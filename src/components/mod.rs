@@ -1,3 +1,7 @@
+pub mod avatar;
 pub mod password_input;
+pub mod poster;
 
+pub use avatar::{fetch_user_avatar, Avatar};
 pub use password_input::PasswordInput;
+pub use poster::PosterCard;
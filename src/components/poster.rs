@@ -0,0 +1,98 @@
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::*;
+use std::sync::Arc;
+
+/// A titled card for an item, with an optional watch progress bar along the
+/// bottom of the picture.
+#[derive(IntoElement)]
+pub struct PosterCard {
+    id: ElementId,
+    title: SharedString,
+    subtitle: Option<SharedString>,
+    image: Option<Arc<RenderImage>>,
+    progress: Option<f32>,
+    size: Size<Pixels>,
+    on_click: Option<Box<dyn Fn(&ClickEvent, &mut Window, &mut App) + 'static>>,
+}
+
+impl PosterCard {
+    pub const PORTRAIT: Size<Pixels> = Size { width: px(150.), height: px(225.) };
+    pub const LANDSCAPE: Size<Pixels> = Size { width: px(256.), height: px(144.) };
+
+    pub fn new(id: impl Into<ElementId>, title: impl Into<SharedString>, image: Option<Arc<RenderImage>>) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            subtitle: None,
+            image,
+            progress: None,
+            size: Self::PORTRAIT,
+            on_click: None,
+        }
+    }
+
+    pub fn subtitle(mut self, subtitle: Option<impl Into<SharedString>>) -> Self {
+        self.subtitle = subtitle.map(Into::into);
+        self
+    }
+
+    /// Played fraction between 0 and 1. Nothing is drawn for `None` or 0.
+    pub fn progress(mut self, progress: Option<f32>) -> Self {
+        self.progress = progress.filter(|p| *p > 0.).map(|p| p.min(1.));
+        self
+    }
+
+    pub fn with_size(mut self, size: Size<Pixels>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn on_click(mut self, handler: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static) -> Self {
+        self.on_click = Some(Box::new(handler));
+        self
+    }
+}
+
+impl RenderOnce for PosterCard {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+
+        v_flex()
+            .id(self.id)
+            .w(self.size.width)
+            .flex_none()
+            .gap_1()
+            .child(
+                div()
+                    .relative()
+                    .w(self.size.width)
+                    .h(self.size.height)
+                    .rounded_md()
+                    .overflow_hidden()
+                    .bg(theme.muted)
+                    .when_some(self.image, |this, image| {
+                        this.child(img(image).size_full().object_fit(ObjectFit::Cover))
+                    })
+                    .when_some(self.progress, |this, progress| {
+                        this.child(
+                            div()
+                                .absolute()
+                                .bottom_0()
+                                .left_0()
+                                .w_full()
+                                .h_1()
+                                .bg(theme.background.opacity(0.6))
+                                .child(div().h_full().w(relative(progress)).bg(theme.primary)),
+                        )
+                    }),
+            )
+            .child(div().text_sm().truncate().child(self.title))
+            .when_some(self.subtitle, |this, subtitle| {
+                this.child(div().text_xs().text_color(theme.muted_foreground).truncate().child(subtitle))
+            })
+            .when_some(self.on_click, |this, handler| {
+                this.cursor_pointer().on_click(handler)
+            })
+    }
+}
@@ -1,25 +1,180 @@
-use anyhow::{Context, Result};
+use crate::secrets::SecretStore;
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Schema version written by this build. Bump it together with a new entry
+/// in `MIGRATIONS` whenever a change would stop older files from loading.
+pub const CONFIG_VERSION: u32 = 2;
+
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static CACHE_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// `MIGRATIONS[n]` upgrades a version `n + 1` file to version `n + 2`.
+/// Files from before the `version` field existed count as version 1.
+const MIGRATIONS: &[fn(&mut Value) -> Result<()>] = &[migrate_v1_to_v2];
+
+/// A user that has signed in on a server from this device.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerUser {
+    pub id: String,
+    pub name: String,
+    /// Tag of the user's avatar, used to tell when a cached picture is stale.
+    #[serde(default)]
+    pub primary_image_tag: Option<String>,
+    /// Only ever held in memory; the token itself lives in the secret store
+    /// under `token_ref`.
+    #[serde(default, skip_serializing)]
+    pub access_token: Option<String>,
+    #[serde(default)]
+    pub token_ref: Option<String>,
+}
+
+impl ServerUser {
+    /// Replaces the access token, keeping the secret store in step.
+    pub fn set_access_token(&mut self, server_id: &str, token: Option<String>, store: &dyn SecretStore) -> Result<()> {
+        if let Some(key) = self.token_ref.take() {
+            store.delete(&key)?;
+        }
+        if let Some(token) = &token {
+            let key = format!("{}:{}", server_id, self.id);
+            store.set(&key, token)?;
+            self.token_ref = Some(key);
+        }
+        self.access_token = token;
+        Ok(())
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Server {
     pub id: String,
     pub name: String,
     pub url: String,
-    pub access_token: Option<String>,
-    pub user_id: Option<String>,
+    /// Everyone signed in on this server, in the order they first signed in.
+    #[serde(default)]
+    pub users: Vec<ServerUser>,
+    /// The user the app opens as when this server is selected.
+    #[serde(default)]
+    pub active_user_id: Option<String>,
+}
+
+impl Server {
+    pub fn new(id: String, name: String, url: String) -> Self {
+        Self {
+            id,
+            name,
+            url,
+            users: Vec::new(),
+            active_user_id: None,
+        }
+    }
+
+    pub fn user(&self, user_id: &str) -> Option<&ServerUser> {
+        self.users.iter().find(|u| u.id == user_id)
+    }
+
+    pub fn active_user(&self) -> Option<&ServerUser> {
+        self.active_user_id.as_deref().and_then(|id| self.user(id))
+    }
+
+    /// Adds `user_id` to the signed-in users, or renames the existing entry,
+    /// and returns it.
+    pub fn upsert_user(&mut self, user_id: &str, name: &str, primary_image_tag: Option<String>) -> &mut ServerUser {
+        let index = match self.users.iter().position(|u| u.id == user_id) {
+            Some(index) => index,
+            None => {
+                self.users.push(ServerUser {
+                    id: user_id.to_string(),
+                    name: name.to_string(),
+                    primary_image_tag: None,
+                    access_token: None,
+                    token_ref: None,
+                });
+                self.users.len() - 1
+            }
+        };
+        let user = &mut self.users[index];
+        user.name = name.to_string();
+        user.primary_image_tag = primary_image_tag;
+        user
+    }
+
+    /// Forgets a user and its token. The active user is cleared if it was them.
+    pub fn remove_user(&mut self, user_id: &str, store: &dyn SecretStore) -> Result<()> {
+        if self.active_user_id.as_deref() == Some(user_id) {
+            self.active_user_id = None;
+        }
+        let Some(index) = self.users.iter().position(|u| u.id == user_id) else {
+            return Ok(());
+        };
+        let mut user = self.users.remove(index);
+        user.set_access_token(&self.id, None, store)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Schema version, see `CONFIG_VERSION`.
+    #[serde(default)]
+    pub version: u32,
     pub servers: Vec<Server>,
     pub active_server_id: Option<String>,
+    /// Identifies this installation to every server. Generated once and
+    /// never changed, so the server's device list keeps a single entry.
+    #[serde(default)]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub subtitles: SubtitleStyle,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            servers: Vec::new(),
+            active_server_id: None,
+            device_id: None,
+            subtitles: SubtitleStyle::default(),
+        }
+    }
+}
+
+/// How the player draws text subtitles.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleStyle {
+    /// Font family; `None` uses the interface font.
+    pub font: Option<String>,
+    /// Text size in pixels.
+    pub size: f32,
+    /// Text color as "#rrggbb" or "#rrggbbaa".
+    pub color: String,
+    /// Seconds to show subtitles later than the stream says, or earlier
+    /// when negative. Fixes tracks that are out of sync with the video.
+    pub offset: f64,
+}
+
+impl Default for SubtitleStyle {
+    fn default() -> Self {
+        Self {
+            font: None,
+            size: 32.,
+            color: "#ffffff".to_string(),
+            offset: 0.,
+        }
+    }
 }
 
 impl Config {
-    pub fn load() -> Result<Self> {
+    /// Reads the config file, upgrading it from older schema versions, and
+    /// fills in each user's access token from `store`.
+    /// A missing or empty file gives the default config.
+    pub fn load(store: &dyn SecretStore) -> Result<Self> {
         let config_path = Self::get_config_path()?;
         if !config_path.exists() {
             return Ok(Self::default());
@@ -32,9 +187,79 @@ impl Config {
             return Ok(Self::default());
         }
 
-        serde_json::from_str(&content).context("Failed to parse config file")
+        let (mut config, from_version) = Self::parse(&content)?;
+        // Plain-text tokens go to the secret store before anything is saved,
+        // since the saved file no longer holds them.
+        let moved_tokens = config.load_tokens(store);
+
+        let upgraded = from_version < CONFIG_VERSION;
+        if upgraded {
+            // Older builds can't read the upgraded file, so keep theirs
+            // around, minus the plain-text tokens just moved out of it.
+            let backup = config_path.with_extension(format!("json.v{}.bak", from_version));
+            let mut old: Value = serde_json::from_str(&content).context("Failed to parse config file")?;
+            strip_tokens(&mut old, &config);
+            let old = serde_json::to_string_pretty(&old).context("Failed to serialize config")?;
+            write_atomic(&backup, old.as_bytes()).context("Failed to back up config before upgrading")?;
+        }
+        if upgraded || moved_tokens {
+            // The config in memory is fine, so a failed save is retried by the next one.
+            match config.save() {
+                Ok(()) if upgraded => {
+                    tracing::info!("Upgraded config from version {} to {}", from_version, CONFIG_VERSION)
+                }
+                Ok(()) => {}
+                Err(e) => tracing::warn!("Failed to save the upgraded config: {:#}", e),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Parses a config file of any known version into the current schema,
+    /// along with the version it was.
+    fn parse(content: &str) -> Result<(Self, u32)> {
+        let mut value: Value = serde_json::from_str(content).context("Failed to parse config file")?;
+        let from_version = migrate(&mut value)?;
+        let config = serde_json::from_value(value).context("Failed to parse config file")?;
+        Ok((config, from_version))
+    }
+
+    /// Like `load`, but a file that can't be read is moved aside to a
+    /// timestamped `.bak` next to it instead of being overwritten by the
+    /// next save. The servers in it are lost to this session, not for good.
+    pub fn load_or_backup(store: &dyn SecretStore) -> Self {
+        match Self::load(store) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::error!("Could not load config: {:#}", e);
+                match Self::backup_unreadable() {
+                    Ok(Some(path)) => tracing::error!("Kept the unreadable config at {}", path.display()),
+                    Ok(None) => {}
+                    Err(e) => tracing::error!("Failed to back up the unreadable config: {:#}", e),
+                }
+                Self::default()
+            }
+        }
     }
 
+    fn backup_unreadable() -> Result<Option<PathBuf>> {
+        let config_path = Self::get_config_path()?;
+        if !config_path.exists() {
+            return Ok(None);
+        }
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let mut backup = config_path.with_extension(format!("json.{}.bak", stamp));
+        let mut n = 1;
+        while backup.exists() {
+            backup = config_path.with_extension(format!("json.{}-{}.bak", stamp, n));
+            n += 1;
+        }
+        fs::rename(&config_path, &backup).context("Failed to move config file")?;
+        Ok(Some(backup))
+    }
+
+    /// Writes the config through a temporary file and a rename, so a crash
+    /// mid-write leaves either the old file or the new one, never half of each.
     pub fn save(&self) -> Result<()> {
         let config_path = Self::get_config_path()?;
         if let Some(parent) = config_path.parent() {
@@ -44,14 +269,101 @@ impl Config {
         let content = serde_json::to_string_pretty(self)
             .context("Failed to serialize config")?;
 
-        fs::write(config_path, content).context("Failed to write config file")
+        write_atomic(&config_path, content.as_bytes()).context("Failed to write config file")
     }
 
-    fn get_config_path() -> Result<PathBuf> {
-        let config_dir = dirs::config_dir()
+    /// Points `config_dir` and `cache_dir` somewhere other than the platform
+    /// defaults, so separate instances don't share servers or tokens. Only the
+    /// first call has any effect, and it has to happen before anything loads.
+    pub fn override_dirs(config_dir: Option<PathBuf>, cache_dir: Option<PathBuf>) {
+        if let Some(dir) = config_dir {
+            let _ = CONFIG_DIR_OVERRIDE.set(dir);
+        }
+        if let Some(dir) = cache_dir {
+            let _ = CACHE_DIR_OVERRIDE.set(dir);
+        }
+    }
+
+    pub fn config_dir() -> Result<PathBuf> {
+        if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+            return Ok(dir.clone());
+        }
+        Ok(dirs::config_dir()
             .context("Failed to get config directory")?
+            .join("crabfin"))
+    }
+
+    /// Where downloaded data that can be fetched again is kept.
+    pub fn cache_dir() -> Result<PathBuf> {
+        if let Some(dir) = CACHE_DIR_OVERRIDE.get() {
+            return Ok(dir.clone());
+        }
+        Ok(dirs::cache_dir()
+            .context("Failed to get cache directory")?
+            .join("crabfin"))
+    }
+
+    /// Where the encrypted-file secret store keeps its key: outside the
+    /// config dir, so backing up or syncing that doesn't carry the key along
+    /// with the secrets. On macOS both are the same directory, but there the
+    /// Keychain is used instead. Each `instance_id` gets a key of its own.
+    pub fn key_dir() -> Result<PathBuf> {
+        let dir = dirs::data_local_dir()
+            .context("Failed to get data directory")?
             .join("crabfin");
-        Ok(config_dir.join("config.json"))
+        Ok(match Self::instance_id() {
+            Some(id) => dir.join("instances").join(id),
+            None => dir,
+        })
+    }
+
+    /// Tells apart instances started with a config dir of their own, so that
+    /// their secrets are kept apart as well as their configs. `None` for the
+    /// default instance. Moving the config dir makes it a new instance.
+    pub fn instance_id() -> Option<String> {
+        CONFIG_DIR_OVERRIDE.get().map(PathBuf::as_path).map(instance_id)
+    }
+
+    fn get_config_path() -> Result<PathBuf> {
+        Ok(Self::config_dir()?.join("config.json"))
+    }
+
+    /// Fills in each user's access token from `store`, first moving any
+    /// plain-text token left by an older version into it.
+    /// Returns `true` when tokens were migrated and the config needs saving.
+    fn load_tokens(&mut self, store: &dyn SecretStore) -> bool {
+        let mut migrated = false;
+        for server in &mut self.servers {
+            let server_id = server.id.clone();
+            for user in &mut server.users {
+                match (user.access_token.take(), user.token_ref.clone()) {
+                    (Some(token), None) => {
+                        if let Err(e) = user.set_access_token(&server_id, Some(token), store) {
+                            tracing::warn!("Failed to move token for {} to the secret store: {}", server.name, e);
+                        }
+                        migrated = true;
+                    }
+                    (_, Some(key)) => {
+                        user.access_token = store.get(&key).unwrap_or_else(|e| {
+                            tracing::warn!("Failed to read token for {}: {}", server.name, e);
+                            None
+                        });
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        migrated
+    }
+
+    /// Generates the device id if this config doesn't have one yet.
+    /// Returns `true` when a new id was created and the config needs saving.
+    pub fn ensure_device_id(&mut self) -> bool {
+        if self.device_id.is_some() {
+            return false;
+        }
+        self.device_id = Some(cuid2::create_id());
+        true
     }
 
     pub fn add_server(&mut self, server: Server) {
@@ -64,3 +376,267 @@ impl Config {
         })
     }
 }
+
+/// A name for the instance using `config_dir`. FNV-1a of the absolute path,
+/// as it has to come out the same from every build.
+fn instance_id(config_dir: &Path) -> String {
+    let dir = std::path::absolute(config_dir).unwrap_or_else(|_| config_dir.to_path_buf());
+    let hash = dir
+        .as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// Brings a parsed config file up to `CONFIG_VERSION` and returns the
+/// version it started at.
+fn migrate(config: &mut Value) -> Result<u32> {
+    let version = config.get("version").and_then(Value::as_u64).unwrap_or(1) as u32;
+    if version > CONFIG_VERSION {
+        return Err(anyhow!(
+            "Config file is version {}, but this build only understands up to {}",
+            version,
+            CONFIG_VERSION
+        ));
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version.saturating_sub(1) as usize) {
+        migration(config).with_context(|| format!("Failed to migrate config from version {}", from + 1))?;
+    }
+    if let Some(config) = config.as_object_mut() {
+        config.insert("version".to_string(), json!(CONFIG_VERSION));
+    }
+    Ok(version)
+}
+
+/// Version 1 kept one `user_id` and token on each server; version 2 keeps a
+/// list of signed-in users. Tokens stay under the same secret store key.
+fn migrate_v1_to_v2(config: &mut Value) -> Result<()> {
+    let Some(servers) = config.get_mut("servers").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+
+    for server in servers {
+        let server = server.as_object_mut().ok_or_else(|| anyhow!("Server entry is not an object"))?;
+        let user_id = server.remove("user_id");
+        let access_token = server.remove("access_token").unwrap_or(Value::Null);
+        let token_ref = server.remove("token_ref").unwrap_or(Value::Null);
+
+        // Without a token the user was signed out; without an id there's
+        // nobody to attach the token to.
+        let Some(Value::String(user_id)) = user_id else {
+            continue;
+        };
+        if access_token.is_null() && token_ref.is_null() {
+            continue;
+        }
+
+        // The name wasn't kept. The user picker looks it up with the token,
+        // and signing in stores it again.
+        server.insert(
+            "users".to_string(),
+            json!([{ "id": user_id, "name": "", "access_token": access_token, "token_ref": token_ref }]),
+        );
+        server.insert("active_user_id".to_string(), json!(user_id));
+    }
+    Ok(())
+}
+
+/// Replaces the plain-text tokens in a config file of an older version with
+/// references to where `config`, loaded from it, keeps them in the secret
+/// store. Version 1 kept the user and token on the server entry itself.
+fn strip_tokens(file: &mut Value, config: &Config) {
+    let Some(servers) = file.get_mut("servers").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    for server in servers.iter_mut().filter_map(Value::as_object_mut) {
+        if !server.get("access_token").is_some_and(Value::is_string) {
+            continue;
+        }
+        let stored = server
+            .get("id")
+            .and_then(Value::as_str)
+            .and_then(|id| config.servers.iter().find(|s| s.id == id));
+        let token_ref = server
+            .get("user_id")
+            .and_then(Value::as_str)
+            .and_then(|user_id| stored?.user(user_id)?.token_ref.clone());
+        server.insert("access_token".to_string(), Value::Null);
+        server.insert("token_ref".to_string(), json!(token_ref));
+    }
+}
+
+/// Writes `contents` to a sibling temporary file, flushes it to disk and
+/// renames it over `path`.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MemoryStore(RefCell<HashMap<String, String>>);
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, key: &str) -> Result<Option<String>> {
+            Ok(self.0.borrow().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, secret: &str) -> Result<()> {
+            self.0.borrow_mut().insert(key.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<()> {
+            self.0.borrow_mut().remove(key);
+            Ok(())
+        }
+    }
+
+    /// A version 1 file: one user per server, with the token in plain text
+    /// from before the secret store, or already moved into it.
+    const V1: &str = r#"{
+        "servers": [
+            {
+                "id": "srv1",
+                "name": "Home",
+                "url": "http://192.168.1.5:8096",
+                "access_token": "plain-token",
+                "token_ref": null,
+                "user_id": "user1"
+            },
+            {
+                "id": "srv2",
+                "name": "Cabin",
+                "url": "https://cabin.example.com",
+                "token_ref": "srv2:user2",
+                "user_id": "user2"
+            },
+            {
+                "id": "srv3",
+                "name": "Signed out",
+                "url": "http://old.lan:8096",
+                "access_token": null,
+                "token_ref": null,
+                "user_id": "user3"
+            }
+        ],
+        "active_server_id": "srv1",
+        "device_id": "device"
+    }"#;
+
+    const V2: &str = r#"{
+        "version": 2,
+        "servers": [
+            {
+                "id": "srv1",
+                "name": "Home",
+                "url": "http://192.168.1.5:8096",
+                "users": [
+                    { "id": "user1", "name": "Alice", "primary_image_tag": "tag1", "token_ref": "srv1:user1" },
+                    { "id": "user2", "name": "Bob", "token_ref": null }
+                ],
+                "active_user_id": "user1"
+            }
+        ],
+        "active_server_id": "srv1",
+        "device_id": "device",
+        "subtitles": { "size": 40.0 }
+    }"#;
+
+    #[test]
+    fn migrates_v1_to_v2() {
+        let (config, from_version) = Config::parse(V1).unwrap();
+        assert_eq!(from_version, 1);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.active_server_id.as_deref(), Some("srv1"));
+        assert_eq!(config.device_id.as_deref(), Some("device"));
+
+        let home = &config.servers[0];
+        assert_eq!(home.active_user_id.as_deref(), Some("user1"));
+        assert_eq!(home.users.len(), 1);
+        assert_eq!(home.users[0].id, "user1");
+        assert_eq!(home.users[0].access_token.as_deref(), Some("plain-token"));
+        assert_eq!(home.users[0].token_ref, None);
+
+        let cabin = &config.servers[1];
+        assert_eq!(cabin.active_user_id.as_deref(), Some("user2"));
+        assert_eq!(cabin.users[0].token_ref.as_deref(), Some("srv2:user2"));
+
+        // Signed out, so there's no one to keep.
+        assert!(config.servers[2].users.is_empty());
+        assert_eq!(config.servers[2].active_user_id, None);
+    }
+
+    #[test]
+    fn v2_loads_as_is() {
+        let (config, from_version) = Config::parse(V2).unwrap();
+        assert_eq!(from_version, 2);
+
+        let server = &config.servers[0];
+        assert_eq!(server.active_user_id.as_deref(), Some("user1"));
+        assert_eq!(server.users.len(), 2);
+        assert_eq!(server.users[0].name, "Alice");
+        assert_eq!(server.users[0].primary_image_tag.as_deref(), Some("tag1"));
+        assert_eq!(server.users[0].token_ref.as_deref(), Some("srv1:user1"));
+        assert_eq!(server.users[1].name, "Bob");
+        assert_eq!(config.subtitles.size, 40.);
+        assert_eq!(config.subtitles.color, SubtitleStyle::default().color);
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let newer = format!(r#"{{ "version": {}, "servers": [], "active_server_id": null }}"#, CONFIG_VERSION + 1);
+        assert!(Config::parse(&newer).is_err());
+    }
+
+    #[test]
+    fn instance_ids_follow_the_config_dir() {
+        let id = instance_id(Path::new("/home/kiosk/crabfin-a"));
+        assert_eq!(id, instance_id(Path::new("/home/kiosk/crabfin-a")));
+        assert_ne!(id, instance_id(Path::new("/home/kiosk/crabfin-b")));
+        assert_eq!(id.len(), 16);
+    }
+
+    #[test]
+    fn upgrade_moves_plain_text_tokens() {
+        let dir = std::env::temp_dir().join(format!("crabfin-config-upgrade-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config.json"), V1).unwrap();
+        Config::override_dirs(Some(dir.clone()), None);
+
+        let store = MemoryStore::default();
+        let config = Config::load(&store).unwrap();
+        assert_eq!(config.servers[0].users[0].access_token.as_deref(), Some("plain-token"));
+        assert_eq!(store.get("srv1:user1").unwrap().as_deref(), Some("plain-token"));
+
+        // The saved file refers to the stored token rather than holding it.
+        let saved = fs::read_to_string(dir.join("config.json")).unwrap();
+        assert!(!saved.contains("plain-token"));
+        let (saved, version) = Config::parse(&saved).unwrap();
+        assert_eq!(version, CONFIG_VERSION);
+        assert_eq!(saved.servers[0].users[0].token_ref.as_deref(), Some("srv1:user1"));
+
+        // The backup older builds can read points at the stored token too.
+        let backup = fs::read_to_string(dir.join("config.json.v1.bak")).unwrap();
+        assert!(!backup.contains("plain-token"));
+        let backup: Value = serde_json::from_str(&backup).unwrap();
+        assert_eq!(backup["servers"][0]["token_ref"], "srv1:user1");
+        assert_eq!(backup["servers"][0]["user_id"], "user1");
+        assert_eq!(backup["servers"][1]["token_ref"], "srv2:user2");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,449 @@
+use crate::api::{BaseItemDto, BaseItemKind, BaseItemPerson, GetItemImageQuery, ImageType, JellyfinClient};
+use crate::config::Config;
+use anyhow::{Context as _, Result};
+use gpui::*;
+use image::{Frame, RgbaImage};
+use lru::LruCache;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// JPEG quality asked of the server. Thumbnails don't need more.
+const QUALITY: i32 = 90;
+
+/// Decoded thumbnails are plain BGRA, so the memory cache is bounded by
+/// bytes rather than by count: a backdrop weighs as much as fifty posters.
+const MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+/// The disk cache is trimmed back to this size, least recently used files
+/// first, when a session starts and after every `DISK_TRIM_INTERVAL` written.
+const DISK_BUDGET: u64 = 512 * 1024 * 1024;
+const DISK_TRIM_INTERVAL: u64 = 32 * 1024 * 1024;
+
+/// How long an image that failed to load is left alone before it's asked
+/// for again, so a brief outage doesn't leave it blank all session.
+const FAILED_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+const PLACEHOLDER_SIZE: u32 = 32;
+const PLACEHOLDER_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(512).unwrap();
+
+/// One image of one item, enough to fetch it and to key a cache by.
+///
+/// The blur hash is carried along for the placeholder but isn't part of the
+/// identity: the same image reached through different items is one entry.
+#[derive(Debug, Clone)]
+pub struct Artwork {
+    pub item_id: String,
+    pub image_type: ImageType,
+    pub tag: Option<String>,
+    pub blur_hash: Option<String>,
+}
+
+impl PartialEq for Artwork {
+    fn eq(&self, other: &Self) -> bool {
+        self.item_id == other.item_id && self.image_type == other.image_type && self.tag == other.tag
+    }
+}
+
+impl Eq for Artwork {}
+
+impl Hash for Artwork {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.item_id.hash(state);
+        self.image_type.hash(state);
+        self.tag.hash(state);
+    }
+}
+
+impl Artwork {
+    /// An image of `owner` as listed on `item`. The server includes the blur
+    /// hashes of parent images too, so the lookup is by tag alone.
+    fn of(item: &BaseItemDto, owner: &str, image_type: ImageType, tag: &String) -> Self {
+        let hashes = item.image_blur_hashes.as_ref().and_then(|hashes| match image_type {
+            ImageType::Primary => hashes.primary.as_ref(),
+            ImageType::Backdrop => hashes.backdrop.as_ref(),
+            ImageType::Thumb => hashes.thumb.as_ref(),
+            // Logos are mostly transparent; blurred they're just a smudge.
+            _ => None,
+        });
+        Self {
+            item_id: owner.to_string(),
+            image_type,
+            tag: Some(tag.clone()),
+            blur_hash: hashes.and_then(|hashes| hashes.get(tag)).cloned(),
+        }
+    }
+
+    /// The image for a tall 2:3 card: the item's own poster, or the series
+    /// poster for an episode.
+    pub fn portrait(item: &BaseItemDto) -> Option<Self> {
+        let id = item.id.as_deref()?;
+        if let Some(tag) = item.image_tags.as_ref().and_then(|tags| tags.get("Primary")) {
+            return Some(Self::of(item, id, ImageType::Primary, tag));
+        }
+        let series_id = item.series_id.as_deref()?;
+        let tag = item.series_primary_image_tag.as_ref()?;
+        Some(Self::of(item, series_id, ImageType::Primary, tag))
+    }
+
+    /// The image for a wide 16:9 card. Episodes use their own screenshot;
+    /// everything else prefers a thumb, then a backdrop, then the poster.
+    pub fn landscape(item: &BaseItemDto) -> Option<Self> {
+        let id = item.id.as_deref()?;
+        let tags = item.image_tags.as_ref();
+        if item.type_ == Some(BaseItemKind::Episode)
+            && let Some(tag) = tags.and_then(|tags| tags.get("Primary"))
+        {
+            return Some(Self::of(item, id, ImageType::Primary, tag));
+        }
+        if let Some(tag) = tags.and_then(|tags| tags.get("Thumb")) {
+            return Some(Self::of(item, id, ImageType::Thumb, tag));
+        }
+        if let (Some(parent), Some(tag)) = (item.parent_thumb_item_id.as_deref(), item.parent_thumb_image_tag.as_ref()) {
+            return Some(Self::of(item, parent, ImageType::Thumb, tag));
+        }
+        if let Some(tag) = item.backdrop_image_tags.as_ref().and_then(|tags| tags.first()) {
+            return Some(Self::of(item, id, ImageType::Backdrop, tag));
+        }
+        if let Some(tag) = tags.and_then(|tags| tags.get("Primary")) {
+            return Some(Self::of(item, id, ImageType::Primary, tag));
+        }
+        None
+    }
+
+    /// The item's own backdrop, or its parent's for episodes and seasons.
+    pub fn backdrop(item: &BaseItemDto) -> Option<Self> {
+        let id = item.id.as_deref()?;
+        if let Some(tag) = item.backdrop_image_tags.as_ref().and_then(|tags| tags.first()) {
+            return Some(Self::of(item, id, ImageType::Backdrop, tag));
+        }
+        let parent = item.parent_backdrop_item_id.as_deref()?;
+        let tag = item.parent_backdrop_image_tags.as_ref().and_then(|tags| tags.first())?;
+        Some(Self::of(item, parent, ImageType::Backdrop, tag))
+    }
+
+    /// The title logo, falling back to the series logo like [`Self::backdrop`].
+    pub fn logo(item: &BaseItemDto) -> Option<Self> {
+        let id = item.id.as_deref()?;
+        if let Some(tag) = item.image_tags.as_ref().and_then(|tags| tags.get("Logo")) {
+            return Some(Self::of(item, id, ImageType::Logo, tag));
+        }
+        let parent = item.parent_logo_item_id.as_deref()?;
+        let tag = item.parent_logo_image_tag.as_ref()?;
+        Some(Self::of(item, parent, ImageType::Logo, tag))
+    }
+
+    /// A cast or crew member's photo.
+    pub fn person(person: &BaseItemPerson) -> Option<Self> {
+        let id = person.id.as_deref()?;
+        let tag = person.primary_image_tag.as_ref()?;
+        let blur_hash = person
+            .image_blur_hashes
+            .as_ref()
+            .and_then(|hashes| hashes.primary.as_ref())
+            .and_then(|hashes| hashes.get(tag))
+            .cloned();
+        Some(Self {
+            item_id: id.to_string(),
+            image_type: ImageType::Primary,
+            tag: Some(tag.clone()),
+            blur_hash,
+        })
+    }
+
+    /// Where the downloaded bytes live on disk. Only tagged images are kept,
+    /// since the tag changes whenever the image does.
+    fn disk_path(&self, dir: &Path, width: u32) -> Option<PathBuf> {
+        let tag = self.tag.as_deref().filter(|tag| is_safe_name(tag))?;
+        if !is_safe_name(&self.item_id) {
+            return None;
+        }
+        let file = format!("{}-{}-{}", self.image_type.as_str(), tag, width);
+        Some(dir.join(&self.item_id).join(file))
+    }
+}
+
+/// Ids and tags are hex from the server, but they end up in file paths.
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ImageKey {
+    artwork: Artwork,
+    width: u32,
+}
+
+struct Cache {
+    thumbnails: LruCache<ImageKey, Arc<RenderImage>>,
+    bytes: usize,
+    placeholders: LruCache<String, Arc<RenderImage>>,
+    pending: HashSet<ImageKey>,
+    /// Images the server couldn't give us, and when; not asked for again
+    /// until `FAILED_RETRY_AFTER` has passed.
+    failed: HashMap<ImageKey, Instant>,
+    /// Bytes written to the disk cache since it was last trimmed.
+    disk_written: u64,
+}
+
+impl Cache {
+    /// Stores a thumbnail and returns whatever had to go to stay in budget.
+    fn insert(&mut self, key: ImageKey, image: Arc<RenderImage>) -> Vec<Arc<RenderImage>> {
+        self.bytes += image_bytes(&image);
+        let mut evicted = Vec::new();
+        if let Some(old) = self.thumbnails.put(key, image) {
+            self.bytes -= image_bytes(&old);
+            evicted.push(old);
+        }
+        while self.bytes > MEMORY_BUDGET && self.thumbnails.len() > 1 {
+            let Some((_, old)) = self.thumbnails.pop_lru() else { break };
+            self.bytes -= image_bytes(&old);
+            evicted.push(old);
+        }
+        evicted
+    }
+}
+
+/// Loads item artwork for every view of a session.
+///
+/// Images are scaled on the server to the width they're shown at, kept on
+/// disk as downloaded so they survive restarts, and kept decoded in memory
+/// so scrolling back doesn't decode again. Clones share the caches.
+#[derive(Clone)]
+pub struct ImageService {
+    client: JellyfinClient,
+    disk_dir: Option<PathBuf>,
+    cache: Arc<Mutex<Cache>>,
+}
+
+impl ImageService {
+    pub fn new(client: JellyfinClient) -> Self {
+        let disk_dir = match Config::cache_dir() {
+            Ok(dir) => Some(dir.join("images")),
+            Err(e) => {
+                tracing::warn!("Image disk cache disabled: {}", e);
+                None
+            }
+        };
+        if let Some(dir) = disk_dir.clone() {
+            trim_disk_cache_in_background(dir);
+        }
+        Self {
+            client,
+            disk_dir,
+            cache: Arc::new(Mutex::new(Cache {
+                thumbnails: LruCache::unbounded(),
+                bytes: 0,
+                placeholders: LruCache::new(PLACEHOLDER_CACHE_SIZE),
+                pending: HashSet::new(),
+                failed: HashMap::new(),
+                disk_written: 0,
+            })),
+        }
+    }
+
+    /// The image to draw for `artwork` shown `size` wide: the thumbnail when
+    /// it's ready, otherwise its blur hash while the thumbnail loads. The view
+    /// is notified once the thumbnail arrives.
+    pub fn image<V: 'static>(
+        &self,
+        artwork: Option<&Artwork>,
+        size: Pixels,
+        cx: &Context<V>,
+    ) -> Option<Arc<RenderImage>> {
+        let artwork = artwork?;
+        // Twice the shown width keeps images sharp on HiDPI screens.
+        let key = ImageKey {
+            artwork: artwork.clone(),
+            width: (f32::from(size) * 2.).round() as u32,
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(image) = cache.thumbnails.get(&key) {
+            return Some(image.clone());
+        }
+        let failed_recently = cache.failed.get(&key).is_some_and(|at| at.elapsed() < FAILED_RETRY_AFTER);
+        if !failed_recently && cache.pending.insert(key.clone()) {
+            cache.failed.remove(&key);
+            self.load(key, cx);
+        }
+        let hash = artwork.blur_hash.as_ref()?;
+        if let Some(image) = cache.placeholders.get(hash) {
+            return Some(image.clone());
+        }
+        let placeholder = decode_blur_hash(hash)?;
+        cache.placeholders.put(hash.clone(), placeholder.clone());
+        Some(placeholder)
+    }
+
+    fn load<V: 'static>(&self, key: ImageKey, cx: &Context<V>) {
+        let this = self.clone();
+        cx.spawn(async move |view, cx| {
+            let image = this.fetch(&key, cx).await;
+            let evicted = {
+                let mut cache = this.cache.lock().unwrap();
+                cache.pending.remove(&key);
+                match image {
+                    Ok(image) => cache.insert(key, image),
+                    Err(e) => {
+                        tracing::debug!("Failed to load image of {}: {:#}", key.artwork.item_id, e);
+                        cache.failed.insert(key, Instant::now());
+                        return;
+                    }
+                }
+            };
+            let _ = view.update(cx, |_, cx| {
+                // Evicted thumbnails still hold a texture in each window.
+                for image in evicted {
+                    cx.drop_image(image, None);
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Reads the image from disk, or downloads it and writes it there.
+    /// Decoding happens off the main thread.
+    async fn fetch(&self, key: &ImageKey, cx: &AsyncApp) -> Result<Arc<RenderImage>> {
+        let path = self.disk_dir.as_deref().and_then(|dir| key.artwork.disk_path(dir, key.width));
+
+        if let Some(path) = path.clone() {
+            let cached = cx
+                .background_spawn(async move {
+                    let bytes = fs::read(&path).ok()?;
+                    // Trimming goes by modification time, so a hit counts as a use.
+                    let _ = fs::File::options().write(true).open(&path).and_then(|f| f.set_modified(SystemTime::now()));
+                    Some(bytes)
+                })
+                .await;
+            if let Some(bytes) = cached
+                && let Ok(image) = cx.background_spawn(async move { decode(&bytes) }).await
+            {
+                return Ok(image);
+            }
+        }
+
+        let query = GetItemImageQuery {
+            tag: key.artwork.tag.clone(),
+            max_width: Some(key.width as i32),
+            quality: Some(QUALITY),
+            ..Default::default()
+        };
+        let bytes = self
+            .client
+            .get_item_image(&key.artwork.item_id, key.artwork.image_type, &query)
+            .await?;
+
+        let this = self.clone();
+        cx.background_spawn(async move {
+            let image = decode(&bytes)?;
+            if let Some(path) = path {
+                match write_cache_file(&path, &bytes) {
+                    Ok(()) => this.note_disk_write(bytes.len() as u64),
+                    Err(e) => tracing::debug!("Failed to cache image at {}: {}", path.display(), e),
+                }
+            }
+            Ok(image)
+        })
+        .await
+    }
+
+    /// Trims the disk cache once enough has been written since the last time.
+    fn note_disk_write(&self, len: u64) {
+        let trim = {
+            let mut cache = self.cache.lock().unwrap();
+            cache.disk_written += len;
+            let trim = cache.disk_written >= DISK_TRIM_INTERVAL;
+            if trim {
+                cache.disk_written = 0;
+            }
+            trim
+        };
+        if trim && let Some(dir) = self.disk_dir.clone() {
+            trim_disk_cache_in_background(dir);
+        }
+    }
+}
+
+fn write_cache_file(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, bytes)
+}
+
+fn trim_disk_cache_in_background(dir: PathBuf) {
+    std::thread::spawn(move || {
+        if let Err(e) = trim_disk_cache(&dir, DISK_BUDGET) {
+            tracing::debug!("Failed to trim the image cache at {}: {}", dir.display(), e);
+        }
+    });
+}
+
+/// Deletes the least recently used files under `dir` until the rest fit in
+/// `budget` bytes. Files are kept per item, one directory each.
+fn trim_disk_cache(dir: &Path, budget: u64) -> std::io::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    let mut files = Vec::new();
+    let mut total = 0;
+    for item_dir in fs::read_dir(dir)?.flatten() {
+        let Ok(entries) = fs::read_dir(item_dir.path()) else { continue };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else { continue };
+            if metadata.is_file() {
+                total += metadata.len();
+                files.push((metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len(), entry.path()));
+            }
+        }
+    }
+    if total <= budget {
+        return Ok(());
+    }
+
+    files.sort_unstable_by_key(|(modified, ..)| *modified);
+    for (_, len, path) in files {
+        if total <= budget {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+            // Only goes through once the item's last image is gone.
+            if let Some(item_dir) = path.parent() {
+                let _ = fs::remove_dir(item_dir);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode(bytes: &[u8]) -> Result<Arc<RenderImage>> {
+    let image = image::load_from_memory(bytes).context("Unsupported image data")?;
+    Ok(render_image(image.into_rgba8()))
+}
+
+/// A tiny blurred preview to show while the real image loads.
+fn decode_blur_hash(hash: &str) -> Option<Arc<RenderImage>> {
+    let pixels = blurhash::decode(hash, PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, 1.0).ok()?;
+    let image = RgbaImage::from_raw(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, pixels)?;
+    Some(render_image(image))
+}
+
+/// GPUI draws BGRA, so the red and blue channels swap places.
+fn render_image(mut image: RgbaImage) -> Arc<RenderImage> {
+    for pixel in image.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    Arc::new(RenderImage::new(vec![Frame::new(image)]))
+}
+
+fn image_bytes(image: &RenderImage) -> usize {
+    let size = image.size(0);
+    size.width.0 as usize * size.height.0 as usize * 4
+}
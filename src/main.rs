@@ -1,41 +1,541 @@
+mod cli;
 mod config;
 mod views;
 mod components;
+mod images;
+mod player;
 mod state;
 mod api;
+mod router;
+mod secrets;
 
-use config::Server;
+use api::{ApiError, JellyfinClient, UserDto};
+use clap::Parser;
+use config::{Config, Server};
+use images::ImageService;
+use player::reporting::PlaybackReporter;
 use gpui::*;
 use gpui_component::*;
-use state::AppState;
-use views::{AddServerView, LoginView, ServerListView};
+use router::{GoBack, GoForward, Router};
+use state::{AppState, Screen};
+use std::time::Duration;
+use views::{AddServerView, HomeView, ItemView, LibraryView, LoginView, PlayerView, SearchView, ServerListView, SettingsView, UserPickerView};
+
+const QUICK_CONNECT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The signed-in user the content screens are shown for.
+struct Session {
+    server: Server,
+    client: JellyfinClient,
+    images: ImageService,
+    reporter: PlaybackReporter,
+    user: UserDto,
+}
 
 struct CrabfinApp {
     state: Entity<AppState>,
-    active_view: AnyView,
+    router: Router,
+    session: Option<Session>,
+    /// Item from `--item`, opened once the first session is up.
+    pending_item_id: Option<String>,
 }
 
 impl CrabfinApp {
-    fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+    fn new(args: cli::Args, window: &mut Window, cx: &mut Context<Self>) -> Self {
         let state = cx.new(|_| AppState::new());
-        let weak_app = cx.weak_entity();
-
-        let active_view = if state.read(cx).config.servers.is_empty() {
-            Self::create_add_server_view(weak_app.clone(), window, cx)
-        } else {
-            let servers = state.read(cx).config.servers.clone();
-            Self::create_server_list_view(weak_app.clone(), servers, window, cx)
-        };
 
         cx.observe(&state, |_, _, cx| {
             cx.notify();
         }).detach();
 
-        Self { state, active_view }
+        let active = state.read(cx).config.get_active_server().and_then(|server| {
+            let user = server.active_user().filter(|u| u.access_token.is_some())?;
+            Some((server.clone(), user.id.clone()))
+        });
+        if let Some(url) = args.server {
+            cx.defer_in(window, move |app, window, cx| app.open_server_url(url, window, cx));
+        } else if let Some((server, user_id)) = active {
+            cx.defer_in(window, move |app, window, cx| app.resume_session(server, user_id, window, cx));
+        }
+
+        let mut app = Self {
+            state,
+            router: Router::new(),
+            session: None,
+            pending_item_id: args.item,
+        };
+        app.show_server_list(window, cx);
+        app
+    }
+
+    /// Opens `screen` on top of the history.
+    fn navigate(&mut self, screen: Screen, window: &mut Window, cx: &mut Context<Self>) {
+        match screen {
+            Screen::ServerList => self.show_server_list(window, cx),
+            Screen::AddServer => {
+                let view = Self::create_add_server_view(cx.weak_entity(), window, cx);
+                self.router.push(Screen::AddServer, view);
+                cx.notify();
+            }
+            Screen::Login(url) => {
+                self.show_login(url, window, cx);
+            }
+            Screen::UserPicker(server_id) => self.show_user_picker(&server_id, window, cx),
+            Screen::Home => match self.create_home_view(cx) {
+                Some(view) => {
+                    self.router.push(Screen::Home, view);
+                    cx.notify();
+                }
+                None => self.show_server_list(window, cx),
+            },
+            Screen::Library(library_id) => match self.create_library_view(library_id.clone(), cx) {
+                Some(view) => {
+                    self.router.push(Screen::Library(library_id), view);
+                    cx.notify();
+                }
+                None => self.show_server_list(window, cx),
+            },
+            Screen::Item(item_id) => match self.create_item_view(item_id.clone(), cx) {
+                Some(view) => {
+                    self.router.push(Screen::Item(item_id), view);
+                    cx.notify();
+                }
+                None => self.show_server_list(window, cx),
+            },
+            Screen::Search => match self.create_search_view(window, cx) {
+                Some(view) => {
+                    self.router.push(Screen::Search, view);
+                    cx.notify();
+                }
+                None => self.show_server_list(window, cx),
+            },
+            Screen::Player(item_id) => match self.create_player_view(item_id.clone(), window, cx) {
+                Some(view) => {
+                    self.router.push(Screen::Player(item_id), view);
+                    cx.notify();
+                }
+                None => self.show_server_list(window, cx),
+            },
+            Screen::Settings => {
+                let view = self.create_settings_view(window, cx);
+                self.router.push(Screen::Settings, view);
+                cx.notify();
+            }
+        }
+    }
+
+    fn go_back(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // Playback may have gone fullscreen; the rest of the app doesn't.
+        if matches!(self.router.current_screen(), Some(Screen::Player(_))) && window.is_fullscreen() {
+            window.toggle_fullscreen();
+        }
+        if self.router.back() {
+            cx.notify();
+        }
+    }
+
+    fn go_forward(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.router.forward() {
+            cx.notify();
+        }
+    }
+
+    /// Goes back, or to the server list when there's no history, e.g. when
+    /// the app was started straight into a login form.
+    fn go_back_or_server_list(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.router.can_go_back() {
+            self.go_back(window, cx);
+        } else if !self.state.read(cx).config.servers.is_empty() {
+            self.show_server_list(window, cx);
+        }
+    }
+
+    /// Opens the server given on the command line: straight into its active
+    /// user's session when there is one, otherwise its user picker. A server
+    /// that isn't configured yet goes through the add-server flow.
+    fn open_server_url(&mut self, url: String, window: &mut Window, cx: &mut Context<Self>) {
+        let wanted = url.trim().trim_end_matches('/').to_ascii_lowercase();
+        let server = self
+            .state
+            .read(cx)
+            .config
+            .servers
+            .iter()
+            .find(|s| s.id == wanted || s.url.trim_end_matches('/').to_ascii_lowercase() == wanted)
+            .cloned();
+
+        let Some(server) = server else {
+            let view = Self::create_add_server_view(cx.weak_entity(), window, cx);
+            if let Ok(add_server) = view.clone().downcast::<AddServerView>() {
+                // Connecting reads the app state, which is borrowed until this update returns.
+                window.defer(cx, move |window, cx| {
+                    add_server.update(cx, |view, cx| view.connect_to(url, window, cx));
+                });
+            }
+            self.router.push(Screen::AddServer, view);
+            cx.notify();
+            return;
+        };
+
+        match server.active_user().filter(|u| u.access_token.is_some()).map(|u| u.id.clone()) {
+            Some(user_id) => self.resume_session(server, user_id, window, cx),
+            None => self.show_user_picker(&server.id, window, cx),
+        }
     }
 
-    fn render_active_view(&self, _window: &mut Window, _cx: &mut Context<Self>) -> AnyElement {
-        self.active_view.clone().into_any_element()
+    /// Checks a user's stored token against `/Users/Me`. A valid session goes
+    /// straight to the home screen; a rejected one is cleared and the login
+    /// screen is shown with a notice. Either way the user stays in the
+    /// picker, as only their token has expired, not their account.
+    fn resume_session(&mut self, server: Server, user_id: String, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(user) = server.user(&user_id) else {
+            self.show_login(server.url, window, cx);
+            return;
+        };
+        let name = user.name.clone();
+        let Some(token) = user.access_token.clone() else {
+            let login = self.show_login(server.url, window, cx);
+            login.update(cx, |view, cx| view.prefill_username(name, window, cx));
+            return;
+        };
+        let client = self
+            .state
+            .read(cx)
+            .client_for(&server.url)
+            .map(|client| client.with_access_token(token));
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = match client {
+                Ok(client) => client.get_current_user().await.map(|user| (client, user)),
+                Err(e) => Err(e),
+            };
+
+            let _ = this.update_in(cx, move |app, window, cx| match result {
+                Ok((client, user)) => app.show_home(&server, client, user, window, cx),
+                Err(ApiError::Unauthorized) => {
+                    app.state.update(cx, |state, _cx| {
+                        let stored = state.config.servers.iter_mut().find(|s| s.id == server.id);
+                        if let Some(user) = stored.and_then(|s| s.users.iter_mut().find(|u| u.id == user_id)) {
+                            if let Err(e) = user.set_access_token(&server.id, None, &*state.secrets) {
+                                tracing::warn!("Failed to remove access token: {}", e);
+                            }
+                            let _ = state.config.save();
+                        }
+                    });
+                    let login = app.show_login(server.url.clone(), window, cx);
+                    login.update(cx, |view, cx| {
+                        view.prefill_username(name, window, cx);
+                        view.set_error(Some("Your session has expired. Please sign in again.".to_string()), cx);
+                    });
+                }
+                Err(e) => {
+                    let login = app.show_login(server.url.clone(), window, cx);
+                    login.update(cx, |view, cx| view.set_api_error(&e, cx));
+                }
+            });
+        })
+        .detach();
+    }
+
+    /// Starts a session and opens its home screen. Earlier history belongs
+    /// to whoever was signed in before, so it's dropped.
+    fn show_home(&mut self, server: &Server, client: JellyfinClient, user: UserDto, window: &mut Window, cx: &mut Context<Self>) {
+        let user_id = user.id.clone().unwrap_or_default();
+        self.state.update(cx, |state, _cx| {
+            state.config.active_server_id = Some(server.id.clone());
+            if let Some(stored) = state.config.servers.iter_mut().find(|s| s.id == server.id) {
+                // Keeps the picker's name and avatar in step with the server.
+                stored.upsert_user(&user_id, user.name.as_deref().unwrap_or_default(), user.primary_image_tag.clone());
+                stored.active_user_id = Some(user_id.clone());
+            }
+            let _ = state.config.save();
+        });
+
+        // Also sends whatever reports this user left queued last time.
+        let reporter = PlaybackReporter::new(client.clone(), server.id.clone(), user_id, cx);
+        self.session = Some(Session {
+            server: server.clone(),
+            images: ImageService::new(client.clone()),
+            reporter,
+            client,
+            user,
+        });
+        self.router.clear();
+        self.navigate(Screen::Home, window, cx);
+
+        if let Some(item_id) = self.pending_item_id.take() {
+            self.navigate(Screen::Item(item_id), window, cx);
+        }
+    }
+
+    fn create_home_view(&mut self, cx: &mut Context<Self>) -> Option<AnyView> {
+        let session = self.session.as_ref()?;
+        let client = session.client.clone();
+        let images = session.images.clone();
+        let server = session.server.clone();
+        let user = session.user.clone();
+        let server_id = server.id.clone();
+        let user_id = user.id.clone().unwrap_or_default();
+
+        let weak_app = cx.weak_entity();
+        let view = cx.new(|cx| {
+            HomeView::new(
+                cx,
+                client,
+                images,
+                server,
+                user,
+                {
+                    let weak_app = weak_app.clone();
+                    move |screen, window, cx| {
+                        let _ = weak_app.update(cx, |app, cx| app.navigate(screen, window, cx));
+                    }
+                },
+                move |window, cx| {
+                    let _ = weak_app.update(cx, |app, cx| {
+                        app.logout(&server_id, &user_id, cx);
+                        app.session = None;
+                        app.router.clear();
+                        app.show_user_picker(&server_id, window, cx);
+                    });
+                },
+            )
+        });
+        Some(view.into())
+    }
+
+    fn create_library_view(&mut self, library_id: String, cx: &mut Context<Self>) -> Option<AnyView> {
+        let session = self.session.as_ref()?;
+        let client = session.client.clone();
+        let images = session.images.clone();
+        let user_id = session.user.id.clone();
+
+        let weak_app = cx.weak_entity();
+        let view = cx.new(|cx| {
+            LibraryView::new(
+                cx,
+                client,
+                images,
+                user_id,
+                library_id,
+                {
+                    let weak_app = weak_app.clone();
+                    move |screen, window, cx| {
+                        let _ = weak_app.update(cx, |app, cx| app.navigate(screen, window, cx));
+                    }
+                },
+                move |window, cx| {
+                    let _ = weak_app.update(cx, |app, cx| app.go_back(window, cx));
+                },
+            )
+        });
+        Some(view.into())
+    }
+
+    fn create_item_view(&mut self, item_id: String, cx: &mut Context<Self>) -> Option<AnyView> {
+        let session = self.session.as_ref()?;
+        let client = session.client.clone();
+        let images = session.images.clone();
+        let user_id = session.user.id.clone();
+
+        let weak_app = cx.weak_entity();
+        let view = cx.new(|cx| {
+            ItemView::new(
+                cx,
+                client,
+                images,
+                user_id,
+                item_id,
+                {
+                    let weak_app = weak_app.clone();
+                    move |screen, window, cx| {
+                        let _ = weak_app.update(cx, |app, cx| app.navigate(screen, window, cx));
+                    }
+                },
+                move |window, cx| {
+                    let _ = weak_app.update(cx, |app, cx| app.go_back(window, cx));
+                },
+            )
+        });
+        Some(view.into())
+    }
+
+    fn create_search_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Option<AnyView> {
+        let session = self.session.as_ref()?;
+        let client = session.client.clone();
+        let images = session.images.clone();
+        let user_id = session.user.id.clone();
+
+        let weak_app = cx.weak_entity();
+        let view = cx.new(|cx| {
+            SearchView::new(
+                window,
+                cx,
+                client,
+                images,
+                user_id,
+                {
+                    let weak_app = weak_app.clone();
+                    move |screen, window, cx| {
+                        let _ = weak_app.update(cx, |app, cx| app.navigate(screen, window, cx));
+                    }
+                },
+                move |window, cx| {
+                    let _ = weak_app.update(cx, |app, cx| app.go_back(window, cx));
+                },
+            )
+        });
+        Some(view.into())
+    }
+
+    fn create_player_view(&mut self, item_id: String, window: &mut Window, cx: &mut Context<Self>) -> Option<AnyView> {
+        let session = self.session.as_ref()?;
+        let client = session.client.clone();
+        let reporter = session.reporter.clone();
+        let user_id = session.user.id.clone();
+        let subtitle_style = self.state.read(cx).config.subtitles.clone();
+
+        let weak_app = cx.weak_entity();
+        let view = cx.new(|cx| {
+            PlayerView::new(window, cx, client, reporter, user_id, item_id, subtitle_style, move |window, cx| {
+                let _ = weak_app.update(cx, |app, cx| app.go_back(window, cx));
+            })
+        });
+        Some(view.into())
+    }
+
+    fn create_settings_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> AnyView {
+        let subtitle_style = self.state.read(cx).config.subtitles.clone();
+
+        let weak_app = cx.weak_entity();
+        let view = cx.new(|cx| {
+            SettingsView::new(
+                window,
+                cx,
+                subtitle_style,
+                {
+                    let weak_app = weak_app.clone();
+                    move |style, _window, cx| {
+                        weak_app.update(cx, |app, cx| {
+                            app.state.update(cx, |state, _cx| {
+                                state.config.subtitles = style;
+                                state.config.save()
+                            })
+                        })?
+                    }
+                },
+                move |window, cx| {
+                    let _ = weak_app.update(cx, |app, cx| app.go_back(window, cx));
+                },
+            )
+        });
+        view.into()
+    }
+
+    /// Ends a user's session on the server and forgets them locally.
+    ///
+    /// The local token is cleared even when the server can't be reached, so
+    /// the next login always starts a fresh session; revoking server-side is
+    /// best effort and keeps stale devices off shared servers.
+    fn logout(&mut self, server_id: &str, user_id: &str, cx: &mut Context<Self>) {
+        let client = self.state.update(cx, |state, _cx| {
+            let server = state.config.servers.iter().find(|s| s.id == server_id)?;
+            let token = server.user(user_id).and_then(|u| u.access_token.clone());
+            let client = token.and_then(|token| {
+                state.client_for(&server.url).ok().map(|client| client.with_access_token(token))
+            });
+
+            let server = state.config.servers.iter_mut().find(|s| s.id == server_id)?;
+            if let Err(e) = server.remove_user(user_id, &*state.secrets) {
+                tracing::warn!("Failed to remove access token: {}", e);
+            }
+            let _ = state.config.save();
+            client
+        });
+
+        if let Some(client) = client {
+            cx.spawn(async move |_, _| {
+                if let Err(e) = client.report_session_ended().await {
+                    tracing::warn!("Failed to end session on server: {}", e);
+                }
+            })
+            .detach();
+        }
+    }
+
+    /// Lets the user pick one of the accounts signed in on a server, or the
+    /// login screen when there are none.
+    fn show_user_picker(&mut self, server_id: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(server) = self.state.read(cx).config.servers.iter().find(|s| s.id == server_id).cloned() else {
+            self.show_server_list(window, cx);
+            return;
+        };
+        if server.users.is_empty() {
+            self.show_login(server.url, window, cx);
+            return;
+        }
+        let client = match self.state.read(cx).client_for(&server.url) {
+            Ok(client) => client,
+            Err(e) => {
+                let login = self.show_login(server.url, window, cx);
+                login.update(cx, |view, cx| view.set_api_error(&e, cx));
+                return;
+            }
+        };
+
+        let weak_app = cx.weak_entity();
+        let view = cx
+            .new(|cx| {
+                UserPickerView::new(
+                    cx,
+                    client,
+                    server.name.clone(),
+                    server.users.clone(),
+                    {
+                        let weak_app = weak_app.clone();
+                        let server = server.clone();
+                        move |user, window, cx| {
+                            let server = server.clone();
+                            let user_id = user.id.clone();
+                            let _ = weak_app.update(cx, |app, cx| app.resume_session(server, user_id, window, cx));
+                        }
+                    },
+                    {
+                        let weak_app = weak_app.clone();
+                        let url = server.url.clone();
+                        move |window, cx| {
+                            let _ = weak_app.update(cx, |app, cx| {
+                                app.show_login(url.clone(), window, cx);
+                            });
+                        }
+                    },
+                    move |window, cx| {
+                        let _ = weak_app.update(cx, |app, cx| app.go_back_or_server_list(window, cx));
+                    },
+                )
+            });
+        self.router.push(Screen::UserPicker(server.id.clone()), view.into());
+        cx.notify();
+    }
+
+    fn show_login(&mut self, url: String, window: &mut Window, cx: &mut Context<Self>) -> Entity<LoginView> {
+        let login = Self::create_login_view(cx.weak_entity(), url.clone(), window, cx);
+        self.router.push(Screen::Login(url), login.clone().into());
+        cx.notify();
+        login
+    }
+
+    /// Shows the configured servers, or the add-server form when there are none.
+    fn show_server_list(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let servers = self.state.read(cx).config.servers.clone();
+        if servers.is_empty() {
+            let view = Self::create_add_server_view(cx.weak_entity(), window, cx);
+            self.router.push(Screen::AddServer, view);
+        } else {
+            let view = Self::create_server_list_view(cx.weak_entity(), servers, window, cx);
+            self.router.push(Screen::ServerList, view);
+        }
+        cx.notify();
     }
 
     fn create_add_server_view(weak_app: WeakEntity<Self>, window: &mut Window, cx: &mut Context<Self>) -> AnyView {
@@ -49,6 +549,12 @@ impl CrabfinApp {
                         let weak_app = weak_app.clone();
                         let url = url.clone();
                         let view = cx.weak_entity();
+                        let Ok((http, device)) = weak_app.read_with(cx, |app, cx| {
+                            let state = app.state.read(cx);
+                            (state.http.clone(), state.device.clone())
+                        }) else {
+                            return;
+                        };
 
                         // Use spawn_in to get AsyncWindowContext which allows access to window and app
                         cx.spawn_in(&*window, |_, mut cx: &mut AsyncWindowContext| {
@@ -60,12 +566,15 @@ impl CrabfinApp {
                                     view.set_error(None, cx);
                                 });
 
-                                match api::validate_server(&url).await {
-                                    Ok(info) => {
+                                match api::resolve_server(&url, &http, &device).await {
+                                    Ok(resolved) => {
                                         if let Some(app_entity) = weak_app.upgrade() {
                                             cx.update_window_entity(&app_entity, |app, window, cx| {
+                                                let url = resolved.url();
+                                                let id = resolved.info.id.unwrap_or_default();
+
                                                 // Check if server already exists
-                                                if app.state.read(cx).config.servers.iter().any(|s| s.url == url) {
+                                                if app.state.read(cx).config.servers.iter().any(|s| s.id == id || s.url == url) {
                                                     let _ = view.update(cx, |view, cx| {
                                                         view.set_validating(false, cx);
                                                         view.set_error(Some("Server already exists".to_string()), cx);
@@ -73,21 +582,17 @@ impl CrabfinApp {
                                                     return;
                                                 }
 
-                                                let server = Server {
-                                                    id: info.id,
-                                                    name: info.server_name,
-                                                    url: url.clone(),
-                                                    access_token: None,
-                                                    user_id: None,
-                                                };
+                                                let name = resolved.info.server_name.unwrap_or_else(|| url.clone());
+                                                let server = Server::new(id, name, url.clone());
 
                                                 app.state.update(cx, |state, cx| {
                                                     state.config.add_server(server);
                                                     let _ = state.config.save();
                                                 });
 
-                                                // Navigate to login
-                                                app.active_view = Self::create_login_view(weak_app.clone(), url, window, cx);
+                                                // The add-server form is done with, so the login form takes its place.
+                                                let login = Self::create_login_view(weak_app.clone(), url.clone(), window, cx);
+                                                app.router.replace(Screen::Login(url), login.into());
                                                 cx.notify();
                                             }).ok();
                                         }
@@ -95,7 +600,7 @@ impl CrabfinApp {
                                     Err(e) => {
                                         let _ = view.update(&mut cx, |view, cx| {
                                             view.set_validating(false, cx);
-                                            view.set_error(Some(format!("Failed to connect: {}", e)), cx);
+                                            view.set_api_error(&e, cx);
                                         });
                                     }
                                 }
@@ -106,13 +611,7 @@ impl CrabfinApp {
                 {
                     let weak_app = weak_app.clone();
                     move |window, cx| {
-                        let _ = weak_app.update(cx, |app, cx| {
-                            let servers = app.state.read(cx).config.servers.clone();
-                            if !servers.is_empty() {
-                                app.active_view = Self::create_server_list_view(weak_app.clone(), servers, window, cx);
-                                cx.notify();
-                            }
-                        });
+                        let _ = weak_app.update(cx, |app, cx| app.go_back_or_server_list(window, cx));
                     }
                 },
             )
@@ -120,9 +619,9 @@ impl CrabfinApp {
             .into()
     }
 
-    fn create_login_view(weak_app: WeakEntity<Self>, url: String, window: &mut Window, cx: &mut Context<Self>) -> AnyView {
+    fn create_login_view(weak_app: WeakEntity<Self>, url: String, window: &mut Window, cx: &mut Context<Self>) -> Entity<LoginView> {
         cx.new(|cx| {
-            LoginView::new(
+            let view = LoginView::new(
                 window,
                 cx,
                 url.clone(),
@@ -133,6 +632,11 @@ impl CrabfinApp {
                         let weak_app = weak_app.clone();
                         let url = url.clone();
                         let view = cx.weak_entity();
+                        let Ok(client) = weak_app
+                            .read_with(cx, |app, cx| app.state.read(cx).client_for(&url))
+                        else {
+                            return;
+                        };
 
                         cx.spawn_in(&*window, |_, mut cx: &mut AsyncWindowContext| {
                             let mut cx = cx.clone();
@@ -142,28 +646,30 @@ impl CrabfinApp {
                                     view.set_error(None, cx);
                                 });
 
-                                match api::authenticate(&url, &username, &password).await {
+                                let result = match client {
+                                    Ok(client) => {
+                                        client
+                                            .authenticate_user_by_name(&api::AuthenticateUserByName {
+                                                username: Some(username),
+                                                pw: Some(password),
+                                            })
+                                            .await
+                                    }
+                                    Err(e) => Err(e),
+                                };
+
+                                match result {
                                     Ok(auth_response) => {
                                         if let Some(app_entity) = weak_app.upgrade() {
                                             cx.update_window_entity(&app_entity, |app, window, cx| {
-                                                app.state.update(cx, |state, _cx| {
-                                                    if let Some(server) = state.config.servers.iter_mut().find(|s| s.url == url) {
-                                                        server.user_id = Some(auth_response.user.id);
-                                                        server.access_token = Some(auth_response.access_token);
-                                                        let _ = state.config.save();
-                                                    }
-                                                });
-
-                                                let servers = app.state.read(cx).config.servers.clone();
-                                                app.active_view = Self::create_server_list_view(weak_app.clone(), servers, window, cx);
-                                                cx.notify();
+                                                app.finish_login(&url, auth_response, window, cx);
                                             }).ok();
                                         }
                                     }
                                     Err(e) => {
                                         let _ = view.update(&mut cx, |view, cx| {
                                             view.set_loading(false, cx);
-                                            view.set_error(Some(format!("Login failed: {}", e)), cx);
+                                            view.set_api_error(&e, cx);
                                         });
                                     }
                                 }
@@ -171,24 +677,182 @@ impl CrabfinApp {
                         }).detach();
                     }
                 },
+                {
+                    let weak_app = weak_app.clone();
+                    let url = url.clone();
+                    move |window, cx| {
+                        let weak_app = weak_app.clone();
+                        let url = url.clone();
+                        let view = cx.weak_entity();
+                        let Ok(client) = weak_app
+                            .read_with(cx, |app, cx| app.state.read(cx).client_for(&url))
+                        else {
+                            return;
+                        };
+
+                        cx.spawn_in(&*window, async move |_, cx| {
+                            let _ = view.update(cx, |view, cx| {
+                                view.set_loading(true, cx);
+                                view.set_error(None, cx);
+                            });
+
+                            let result = match client {
+                                Ok(client) => Self::run_quick_connect(&client, &view, cx).await,
+                                Err(e) => Err(e),
+                            };
+
+                            match result {
+                                Ok(Some(auth_response)) => {
+                                    if let Some(app_entity) = weak_app.upgrade() {
+                                        cx.update_window_entity(&app_entity, |app, window, cx| {
+                                            app.finish_login(&url, auth_response, window, cx);
+                                        }).ok();
+                                    }
+                                }
+                                // Cancelled from the view.
+                                Ok(None) => {}
+                                Err(e) => {
+                                    let _ = view.update(cx, |view, cx| {
+                                        view.set_quick_connect_code(None, cx);
+                                        view.set_loading(false, cx);
+                                        view.set_quick_connect_error(&e, cx);
+                                    });
+                                }
+                            }
+                        }).detach();
+                    }
+                },
                 {
                     let weak_app = weak_app.clone();
                     move |window, cx| {
                         let _ = weak_app.update(cx, |app, cx| {
-                            // If we have servers, go back to list, else add server
-                            let servers = app.state.read(cx).config.servers.clone();
-                            if !servers.is_empty() {
-                                app.active_view = Self::create_server_list_view(weak_app.clone(), servers, window, cx);
+                            if app.router.can_go_back() {
+                                app.go_back(window, cx);
                             } else {
-                                app.active_view = Self::create_add_server_view(weak_app.clone(), window, cx);
+                                app.show_server_list(window, cx);
                             }
-                            cx.notify();
                         });
                     }
                 },
-            )
+            );
+            Self::check_quick_connect(weak_app.clone(), url.clone(), cx);
+            Self::load_public_users(weak_app.clone(), url.clone(), cx);
+            view
         })
-            .into()
+    }
+
+    /// Shows the Quick Connect option once the server confirms it's enabled.
+    fn check_quick_connect(weak_app: WeakEntity<Self>, url: String, cx: &mut Context<LoginView>) {
+        cx.spawn(async move |view, cx| {
+            let Ok(Ok(client)) = weak_app.read_with(cx, |app, cx| app.state.read(cx).client_for(&url)) else {
+                return;
+            };
+            let enabled = client.get_quick_connect_enabled().await.unwrap_or(false);
+            let _ = view.update(cx, |view, cx| view.set_quick_connect_available(enabled, cx));
+        })
+        .detach();
+    }
+
+    /// Fills the login screen's user tiles from `/Users/Public`, then their
+    /// avatars. Any failure just leaves the manual fields.
+    fn load_public_users(weak_app: WeakEntity<Self>, url: String, cx: &mut Context<LoginView>) {
+        cx.spawn(async move |view, cx| {
+            let Ok(Ok(client)) = weak_app.read_with(cx, |app, cx| app.state.read(cx).client_for(&url)) else {
+                return;
+            };
+            let users = match client.get_public_users().await {
+                Ok(users) => users,
+                Err(e) => {
+                    tracing::debug!("No public users from {}: {}", url, e);
+                    return;
+                }
+            };
+
+            let avatars: Vec<(String, Option<String>)> = users
+                .iter()
+                .filter(|u| u.primary_image_tag.is_some())
+                .filter_map(|u| Some((u.id.clone()?, u.primary_image_tag.clone())))
+                .collect();
+            if view.update(cx, |view, cx| view.set_public_users(users, cx)).is_err() {
+                return;
+            }
+
+            for (user_id, tag) in avatars {
+                let Some(image) = components::fetch_user_avatar(&client, &user_id, tag).await else {
+                    continue;
+                };
+                if view.update(cx, |view, cx| view.set_public_user_avatar(user_id, image, cx)).is_err() {
+                    return;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Starts a Quick Connect request, shows its code and polls until another
+    /// signed-in device approves it. Returns `None` if the user cancels.
+    async fn run_quick_connect(
+        client: &api::JellyfinClient,
+        view: &WeakEntity<LoginView>,
+        cx: &mut AsyncWindowContext,
+    ) -> api::Result<Option<api::AuthenticationResult>> {
+        let initiated = client.initiate_quick_connect().await?;
+        let (Some(secret), Some(code)) = (initiated.secret, initiated.code) else {
+            return Err(api::ApiError::Decode(serde::de::Error::custom("Quick Connect response without a code")));
+        };
+
+        let shown = view.update(cx, |view, cx| view.set_quick_connect_code(Some(code.clone()), cx));
+        if shown.is_err() {
+            return Ok(None);
+        }
+
+        let query = api::GetQuickConnectStateQuery { secret: secret.clone() };
+        loop {
+            cx.background_executor().timer(QUICK_CONNECT_POLL_INTERVAL).await;
+
+            let still_waiting = view
+                .read_with(cx, |view, _| view.quick_connect_code() == Some(code.as_str()))
+                .unwrap_or(false);
+            if !still_waiting {
+                return Ok(None);
+            }
+
+            if client.get_quick_connect_state(&query).await?.authenticated == Some(true) {
+                break;
+            }
+        }
+
+        let auth = client.authenticate_with_quick_connect(&api::QuickConnectDto { secret }).await?;
+        Ok(Some(auth))
+    }
+
+    /// Stores the token from a successful login on its server and opens the home screen.
+    fn finish_login(&mut self, url: &str, auth_response: api::AuthenticationResult, window: &mut Window, cx: &mut Context<Self>) {
+        let user = auth_response.user.unwrap_or_default();
+        let user_id = user.id.clone().unwrap_or_default();
+        let server = self.state.update(cx, |state, _cx| {
+            let server = state.config.servers.iter_mut().find(|s| s.url == url)?;
+            let server_id = server.id.clone();
+            let stored = server.upsert_user(&user_id, user.name.as_deref().unwrap_or_default(), user.primary_image_tag.clone());
+            if let Err(e) = stored.set_access_token(&server_id, auth_response.access_token, &*state.secrets) {
+                tracing::error!("Failed to store access token: {}", e);
+            }
+            server.active_user_id = Some(user_id.clone());
+            let server = server.clone();
+            let _ = state.config.save();
+            Some(server)
+        });
+
+        let client = server.as_ref().and_then(|server| {
+            let token = server.user(&user_id)?.access_token.clone()?;
+            let client = self.state.read(cx).client_for(&server.url).ok()?;
+            Some(client.with_access_token(token))
+        });
+
+        match (server, client) {
+            (Some(server), Some(client)) => self.show_home(&server, client, user, window, cx),
+            _ => self.show_server_list(window, cx),
+        }
     }
 
     fn create_server_list_view(weak_app: WeakEntity<Self>, servers: Vec<Server>, _window: &mut Window, cx: &mut Context<Self>) -> AnyView {
@@ -198,52 +862,41 @@ impl CrabfinApp {
                 {
                     let weak_app = weak_app.clone();
                     move |server, window, cx| {
-                        let server_url = server.url.clone();
-                        let access_token = server.access_token.clone();
-
-                        let _ = weak_app.update(cx, |app, cx| {
-                            if access_token.is_some() {
-                                // Already logged in, navigate to main content
-                                // For now, just go to a dummy login view (or dashboard in future)
-                                // Re-using login view for now as placeholder for "Connected" state
-                                app.active_view = Self::create_login_view(weak_app.clone(), server_url, window, cx);
-                            } else {
-                                // Not logged in, navigate to login view
-                                app.active_view = Self::create_login_view(weak_app.clone(), server_url, window, cx);
-                            }
-                            cx.notify();
-                        });
+                        let server_id = server.id.clone();
+                        let _ = weak_app.update(cx, |app, cx| app.show_user_picker(&server_id, window, cx));
                     }
                 },
                 {
                     let weak_app = weak_app.clone();
                     move |window, cx| {
-                        let _ = weak_app.update(cx, |app, cx| {
-                            app.active_view = Self::create_add_server_view(weak_app.clone(), window, cx);
-                            cx.notify();
-                        });
+                        let _ = weak_app.update(cx, |app, cx| app.navigate(Screen::AddServer, window, cx));
                     }
                 },
                 {
                     let weak_app = weak_app.clone();
                     move |server, window, cx| {
+                        let server_id = server.id.clone();
                         let server_url = server.url.clone();
                         let _ = weak_app.update(cx, |app, cx| {
+                            for user in &server.users {
+                                app.logout(&server_id, &user.id, cx);
+                            }
                             app.state.update(cx, |state, _cx| {
                                 if let Some(index) = state.config.servers.iter().position(|s| s.url == server_url) {
                                     state.config.servers.remove(index);
+                                    if state.config.active_server_id.as_deref() == Some(server_id.as_str()) {
+                                        state.config.active_server_id = None;
+                                    }
                                     let _ = state.config.save();
                                 }
                             });
 
-                            // Refresh the view
-                            let servers = app.state.read(cx).config.servers.clone();
-                            if servers.is_empty() {
-                                app.active_view = Self::create_add_server_view(weak_app.clone(), window, cx);
-                            } else {
-                                app.active_view = Self::create_server_list_view(weak_app.clone(), servers, window, cx);
+                            // Screens in the history may belong to the removed server.
+                            if app.session.as_ref().is_some_and(|s| s.server.id == server_id) {
+                                app.session = None;
                             }
-                            cx.notify();
+                            app.router.clear();
+                            app.show_server_list(window, cx);
                         });
                     }
                 },
@@ -254,12 +907,28 @@ impl CrabfinApp {
 }
 
 impl Render for CrabfinApp {
-    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        div().size_full().child(self.render_active_view(window, cx))
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .size_full()
+            .on_action(cx.listener(|this, _: &GoBack, window, cx| this.go_back(window, cx)))
+            .on_action(cx.listener(|this, _: &GoForward, window, cx| this.go_forward(window, cx)))
+            .on_mouse_down(
+                MouseButton::Navigate(NavigationDirection::Back),
+                cx.listener(|this, _, window, cx| this.go_back(window, cx)),
+            )
+            .on_mouse_down(
+                MouseButton::Navigate(NavigationDirection::Forward),
+                cx.listener(|this, _, window, cx| this.go_forward(window, cx)),
+            )
+            .children(self.router.current_view())
     }
 }
 
 fn main() {
+    let args = cli::Args::parse();
+    args.init_logging();
+    Config::override_dirs(args.config_dir.clone(), args.cache_dir.clone());
+
     // Initialize Tokio runtime for reqwest
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -271,6 +940,12 @@ fn main() {
 
     app.run(move |cx| {
         gpui_component::init(cx);
+        cx.bind_keys([
+            KeyBinding::new("alt-left", GoBack, None),
+            KeyBinding::new("alt-right", GoForward, None),
+            KeyBinding::new("cmd-[", GoBack, None),
+            KeyBinding::new("cmd-]", GoForward, None),
+        ]);
 
         cx.spawn(async move |cx| {
             cx.open_window(WindowOptions::default(), |window, cx| {
@@ -282,7 +957,7 @@ fn main() {
 
                     Theme::sync_system_appearance(Some(window), cx);
 
-                    CrabfinApp::new(window, cx)
+                    CrabfinApp::new(args, window, cx)
                 });
                 cx.new(|cx| Root::new(app, window, cx))
             })?;
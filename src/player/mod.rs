@@ -0,0 +1,411 @@
+//! Video playback through libmpv.
+//!
+//! mpv demuxes, decodes in software and scales; each frame is rendered into
+//! a plain pixel buffer that the player view draws as an image. Two threads
+//! sit beside the UI: one turns mpv's events into [`PlayerEvent`]s, the
+//! other renders whenever mpv has a new frame.
+//!
+//! What gets played is agreed with the server first, see [`negotiation`],
+//! and how it goes is told back to it, see [`reporting`].
+
+mod mpv;
+pub mod negotiation;
+mod profile;
+pub mod reporting;
+pub mod subtitles;
+pub mod tracks;
+
+use anyhow::{anyhow, Result};
+use gpui::RenderImage;
+use image::{Frame, RgbaImage};
+use mpv::{Mpv, PropertyValue};
+use std::ffi::{c_int, c_void};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// Frames are rendered at the size they're shown at, up to 4K.
+const MAX_RENDER_SIZE: (u32, u32) = (3840, 2160);
+
+const OPTIONS: &[(&str, &str)] = &[
+    // Frames only go to the render API, never to a window of mpv's own.
+    ("vo", "libmpv"),
+    ("hwdec", "no"),
+    ("idle", "yes"),
+    ("keep-open", "no"),
+    ("osc", "no"),
+    ("input-default-bindings", "no"),
+    ("input-vo-keyboard", "no"),
+    // A user's mpv.conf is meant for their mpv, not for us.
+    ("config", "no"),
+    ("terminal", "no"),
+    ("ytdl", "no"),
+    // Subtitles are drawn by the view, see [`subtitles`].
+    ("sid", "no"),
+];
+
+const PROPERTY_TIME: u64 = 1;
+const PROPERTY_DURATION: u64 = 2;
+const PROPERTY_PAUSE: u64 = 3;
+const PROPERTY_VOLUME: u64 = 4;
+const PROPERTY_MUTE: u64 = 5;
+const PROPERTY_BUFFERING: u64 = 6;
+
+/// What the player reports back to its view. Times are in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerEvent {
+    /// The stream opened and is about to play.
+    Loaded,
+    /// A new frame is ready for [`Player::take_frame`].
+    Frame,
+    Position(f64),
+    Duration(f64),
+    Paused(bool),
+    /// Volume in percent, 0 to 100.
+    Volume(f64),
+    Muted(bool),
+    /// Playback is stalled waiting for the network.
+    Buffering(bool),
+    /// The stream played to its end.
+    Ended,
+    Error(String),
+}
+
+/// State shared with the render thread.
+#[derive(Default)]
+struct RenderState {
+    size: (u32, u32),
+    /// Set by mpv when it has something new to render, or by us on resize.
+    dirty: bool,
+    resized: bool,
+    quit: bool,
+    /// The latest frame, until the view takes it.
+    frame: Option<Arc<RenderImage>>,
+    /// A newer frame is rendered but waits for the view to take `frame`.
+    held: bool,
+}
+
+#[derive(Default)]
+struct Renderer {
+    state: Mutex<RenderState>,
+    wake: Condvar,
+}
+
+/// One mpv instance and the threads that drive it. Dropping the player
+/// stops playback.
+pub struct Player {
+    mpv: Arc<Mpv>,
+    renderer: Arc<Renderer>,
+    stopping: Arc<AtomicBool>,
+    event_thread: Option<JoinHandle<()>>,
+    render_thread: Option<JoinHandle<()>>,
+}
+
+impl Player {
+    /// Starts an idle player. Events arrive on the returned receiver until
+    /// the player is dropped.
+    pub fn new() -> Result<(Self, UnboundedReceiver<PlayerEvent>)> {
+        let mpv = Arc::new(Mpv::new(OPTIONS)?);
+        mpv.observe(PROPERTY_TIME, "time-pos", mpv::MPV_FORMAT_DOUBLE)?;
+        mpv.observe(PROPERTY_DURATION, "duration", mpv::MPV_FORMAT_DOUBLE)?;
+        mpv.observe(PROPERTY_PAUSE, "pause", mpv::MPV_FORMAT_FLAG)?;
+        mpv.observe(PROPERTY_VOLUME, "volume", mpv::MPV_FORMAT_DOUBLE)?;
+        mpv.observe(PROPERTY_MUTE, "mute", mpv::MPV_FORMAT_FLAG)?;
+        mpv.observe(PROPERTY_BUFFERING, "paused-for-cache", mpv::MPV_FORMAT_FLAG)?;
+
+        let (events, receiver) = unbounded_channel();
+        let renderer = Arc::new(Renderer::default());
+        let stopping = Arc::new(AtomicBool::new(false));
+
+        // With `vo=libmpv` a file can only start once the render context
+        // exists, so the player isn't handed out before that.
+        let (ready, created) = std::sync::mpsc::sync_channel(1);
+        let render_thread = {
+            let (mpv, renderer, events) = (mpv.clone(), renderer.clone(), events.clone());
+            std::thread::Builder::new()
+                .name("mpv-render".to_string())
+                .spawn(move || render_loop(&mpv, &renderer, &events, ready))?
+        };
+        created.recv().map_err(|_| anyhow!("mpv: the render thread stopped"))??;
+        let event_thread = {
+            let (mpv, stopping) = (mpv.clone(), stopping.clone());
+            std::thread::Builder::new()
+                .name("mpv-events".to_string())
+                .spawn(move || event_loop(&mpv, &stopping, &events))?
+        };
+
+        Ok((
+            Self {
+                mpv,
+                renderer,
+                stopping,
+                event_thread: Some(event_thread),
+                render_thread: Some(render_thread),
+            },
+            receiver,
+        ))
+    }
+
+    /// Adds a header to every HTTP request mpv makes, e.g. to authenticate
+    /// against the server.
+    pub fn add_http_header(&self, name: &str, value: &str) -> Result<()> {
+        // Appending adds one entry as is, whereas setting the whole list
+        // would split the value at its commas.
+        let header = format!("{}: {}", name, value);
+        self.mpv.command(&["change-list", "http-header-fields", "append", &header])
+    }
+
+    /// Opens `url`, starting `start` seconds in.
+    pub fn load(&self, url: &str, start: f64) -> Result<()> {
+        self.mpv.set_string("start", &format!("+{}", start.max(0.)))?;
+        self.mpv.command(&["loadfile", url, "replace"])
+    }
+
+    /// Switches to the file's audio track `id`, counted from 1, or lets mpv
+    /// pick with `None`. Also applies to the next file loaded.
+    pub fn set_audio_track(&self, id: Option<i64>) -> Result<()> {
+        let id = id.map_or_else(|| "auto".to_string(), |id| id.to_string());
+        self.mpv.set_string("aid", &id)
+    }
+
+    pub fn set_paused(&self, paused: bool) -> Result<()> {
+        self.mpv.set_flag("pause", paused)
+    }
+
+    pub fn seek(&self, seconds: f64) -> Result<()> {
+        self.mpv.command(&["seek", &seconds.max(0.).to_string(), "absolute"])
+    }
+
+    pub fn seek_relative(&self, seconds: f64) -> Result<()> {
+        self.mpv.command(&["seek", &seconds.to_string(), "relative"])
+    }
+
+    pub fn set_volume(&self, percent: f64) -> Result<()> {
+        self.mpv.set_double("volume", percent.clamp(0., 100.))
+    }
+
+    pub fn set_muted(&self, muted: bool) -> Result<()> {
+        self.mpv.set_flag("mute", muted)
+    }
+
+    /// Sets the size in device pixels that frames are rendered at.
+    pub fn set_render_size(&self, width: u32, height: u32) {
+        let size = (width.min(MAX_RENDER_SIZE.0), height.min(MAX_RENDER_SIZE.1));
+        let mut state = self.renderer.state.lock().unwrap();
+        if state.size != size {
+            state.size = size;
+            state.dirty = true;
+            state.resized = true;
+            self.renderer.wake.notify_one();
+        }
+    }
+
+    /// The frame rendered since the last call, if any.
+    pub fn take_frame(&self) -> Option<Arc<RenderImage>> {
+        let mut state = self.renderer.state.lock().unwrap();
+        if state.held {
+            self.renderer.wake.notify_one();
+        }
+        state.frame.take()
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        // The render context has to be freed before mpv is destroyed, which
+        // happens when the last handle goes.
+        {
+            let mut state = self.renderer.state.lock().unwrap();
+            state.quit = true;
+            self.renderer.wake.notify_one();
+        }
+        if let Some(thread) = self.render_thread.take() {
+            let _ = thread.join();
+        }
+
+        self.stopping.store(true, Ordering::Release);
+        self.mpv.wakeup();
+        if let Some(thread) = self.event_thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn event_loop(mpv: &Mpv, stopping: &AtomicBool, events: &UnboundedSender<PlayerEvent>) {
+    while !stopping.load(Ordering::Acquire) {
+        let event = mpv.wait_event(|event| match event.event_id {
+            mpv::MPV_EVENT_SHUTDOWN => None,
+            mpv::MPV_EVENT_FILE_LOADED => Some(Some(PlayerEvent::Loaded)),
+            // SAFETY: the event is live and of the matching kind.
+            mpv::MPV_EVENT_END_FILE => Some(match unsafe { mpv::end_file(event) } {
+                Ok(true) => Some(PlayerEvent::Ended),
+                // Replaced by another file or stopped.
+                Ok(false) => None,
+                Err(e) => Some(PlayerEvent::Error(e.to_string())),
+            }),
+            mpv::MPV_EVENT_PROPERTY_CHANGE => {
+                // SAFETY: as above.
+                let value = unsafe { mpv::property_value(event) };
+                Some(property_event(event.reply_userdata, value))
+            }
+            _ => Some(None),
+        });
+        match event {
+            None => break,
+            Some(Some(event)) => {
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+            Some(None) => {}
+        }
+    }
+}
+
+fn property_event(id: u64, value: PropertyValue) -> Option<PlayerEvent> {
+    match (id, value) {
+        (PROPERTY_TIME, PropertyValue::Double(seconds)) => Some(PlayerEvent::Position(seconds)),
+        (PROPERTY_DURATION, PropertyValue::Double(seconds)) => Some(PlayerEvent::Duration(seconds)),
+        (PROPERTY_PAUSE, PropertyValue::Flag(paused)) => Some(PlayerEvent::Paused(paused)),
+        (PROPERTY_VOLUME, PropertyValue::Double(volume)) => Some(PlayerEvent::Volume(volume)),
+        (PROPERTY_MUTE, PropertyValue::Flag(muted)) => Some(PlayerEvent::Muted(muted)),
+        (PROPERTY_BUFFERING, PropertyValue::Flag(buffering)) => Some(PlayerEvent::Buffering(buffering)),
+        _ => None,
+    }
+}
+
+/// Called by mpv from its own threads, where no mpv function may be called.
+unsafe extern "C" fn on_render_update(renderer: *mut c_void) {
+    // SAFETY: the pointer is the `Renderer` kept alive by the render thread
+    // until the callback is unregistered by freeing the context.
+    let renderer = unsafe { &*(renderer as *const Renderer) };
+    renderer.state.lock().unwrap().dirty = true;
+    renderer.wake.notify_one();
+}
+
+fn render_loop(
+    mpv: &Mpv,
+    renderer: &Arc<Renderer>,
+    events: &UnboundedSender<PlayerEvent>,
+    ready: std::sync::mpsc::SyncSender<Result<()>>,
+) {
+    let mut context = std::ptr::null_mut();
+    let mut params = [
+        mpv::mpv_render_param {
+            type_: mpv::MPV_RENDER_PARAM_API_TYPE,
+            data: mpv::MPV_RENDER_API_TYPE_SW.as_ptr() as *mut c_void,
+        },
+        mpv::mpv_render_param {
+            type_: mpv::MPV_RENDER_PARAM_INVALID,
+            data: std::ptr::null_mut(),
+        },
+    ];
+    // SAFETY: the parameter list is terminated and outlives the call.
+    let created = unsafe { mpv::mpv_render_context_create(&mut context, mpv.raw(), params.as_mut_ptr()) };
+    if created < 0 {
+        let _ = ready.send(Err(anyhow!("mpv: failed to set up rendering ({})", created)));
+        return;
+    }
+    // SAFETY: `renderer` outlives the context, which is freed below.
+    unsafe {
+        mpv::mpv_render_context_set_update_callback(
+            context,
+            Some(on_render_update),
+            Arc::as_ptr(renderer) as *mut c_void,
+        );
+    }
+    let _ = ready.send(Ok(()));
+
+    // mpv renders into the same buffer every time; only frames the view
+    // takes are copied out of it, as each image owns its pixels.
+    let mut pixels = Vec::new();
+    // The size of the frame in `pixels` that the view hasn't had yet.
+    let mut unpublished = None;
+    loop {
+        let (size, resized, render) = {
+            let mut state = renderer.state.lock().unwrap();
+            while !(state.dirty || state.quit || (state.held && state.frame.is_none())) {
+                state = renderer.wake.wait(state).unwrap();
+            }
+            if state.quit {
+                break;
+            }
+            (state.size, std::mem::take(&mut state.resized), std::mem::take(&mut state.dirty))
+        };
+
+        if render {
+            // SAFETY: the context is live and only used from this thread.
+            let flags = unsafe { mpv::mpv_render_context_update(context) };
+            let has_frame = flags & mpv::MPV_RENDER_UPDATE_FRAME != 0;
+            if (has_frame || resized) && size.0 > 0 && size.1 > 0 && render_frame(context, size, &mut pixels) {
+                unpublished = Some(size);
+            }
+        }
+        let Some((width, height)) = unpublished else { continue };
+
+        let mut state = renderer.state.lock().unwrap();
+        // The view hasn't drawn the last frame yet; this one follows once it
+        // has, unless an even newer one replaces it first.
+        state.held = state.frame.is_some();
+        if state.held {
+            continue;
+        }
+        let Some(image) = RgbaImage::from_raw(width, height, pixels.clone()) else { continue };
+        state.frame = Some(Arc::new(RenderImage::new(vec![Frame::new(image)])));
+        drop(state);
+        unpublished = None;
+        if events.send(PlayerEvent::Frame).is_err() {
+            break;
+        }
+    }
+
+    // SAFETY: the context is live; freeing it also unregisters the callback.
+    unsafe { mpv::mpv_render_context_free(context) };
+}
+
+/// Renders the current video frame, scaled and letterboxed to `size`, into
+/// `pixels`, which grows to fit.
+fn render_frame(context: *mut mpv::mpv_render_context, size: (u32, u32), pixels: &mut Vec<u8>) -> bool {
+    let (width, height) = size;
+    let mut dimensions: [c_int; 2] = [width as c_int, height as c_int];
+    let mut stride = width as usize * 4;
+    pixels.resize(stride * height as usize, 0);
+    // "bgr0" is laid out in memory as GPUI expects its images, bar alpha.
+    let format = c"bgr0";
+
+    let mut params = [
+        mpv::mpv_render_param {
+            type_: mpv::MPV_RENDER_PARAM_SW_SIZE,
+            data: dimensions.as_mut_ptr().cast(),
+        },
+        mpv::mpv_render_param {
+            type_: mpv::MPV_RENDER_PARAM_SW_FORMAT,
+            data: format.as_ptr() as *mut c_void,
+        },
+        mpv::mpv_render_param {
+            type_: mpv::MPV_RENDER_PARAM_SW_STRIDE,
+            data: (&raw mut stride).cast(),
+        },
+        mpv::mpv_render_param {
+            type_: mpv::MPV_RENDER_PARAM_SW_POINTER,
+            data: pixels.as_mut_ptr().cast(),
+        },
+        mpv::mpv_render_param {
+            type_: mpv::MPV_RENDER_PARAM_INVALID,
+            data: std::ptr::null_mut(),
+        },
+    ];
+    // SAFETY: every pointer refers to a local that outlives the call, and
+    // the buffer holds `stride * height` bytes.
+    let result = unsafe { mpv::mpv_render_context_render(context, params.as_mut_ptr()) };
+    if result < 0 {
+        tracing::debug!("mpv failed to render a frame: {}", result);
+        return false;
+    }
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = u8::MAX;
+    }
+    true
+}
@@ -0,0 +1,240 @@
+//! Minimal bindings to libmpv's client and software render APIs.
+//!
+//! Only what the player uses is declared. Values follow `mpv/client.h` and
+//! `mpv/render.h` from mpv 0.33 or newer, which added software rendering.
+
+#![allow(non_camel_case_types)]
+
+use anyhow::{anyhow, Result};
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::ptr;
+
+#[repr(C)]
+pub struct mpv_handle {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct mpv_render_context {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct mpv_event {
+    pub event_id: c_int,
+    pub error: c_int,
+    pub reply_userdata: u64,
+    pub data: *mut c_void,
+}
+
+#[repr(C)]
+pub struct mpv_event_property {
+    pub name: *const c_char,
+    pub format: c_int,
+    pub data: *mut c_void,
+}
+
+#[repr(C)]
+pub struct mpv_event_end_file {
+    pub reason: c_int,
+    pub error: c_int,
+    pub playlist_entry_id: i64,
+    pub playlist_insert_id: i64,
+    pub playlist_insert_num_entries: c_int,
+}
+
+#[repr(C)]
+pub struct mpv_render_param {
+    pub type_: c_int,
+    pub data: *mut c_void,
+}
+
+pub const MPV_FORMAT_FLAG: c_int = 3;
+pub const MPV_FORMAT_DOUBLE: c_int = 5;
+
+pub const MPV_EVENT_SHUTDOWN: c_int = 1;
+pub const MPV_EVENT_END_FILE: c_int = 7;
+pub const MPV_EVENT_FILE_LOADED: c_int = 8;
+pub const MPV_EVENT_PROPERTY_CHANGE: c_int = 22;
+
+pub const MPV_END_FILE_REASON_EOF: c_int = 0;
+pub const MPV_END_FILE_REASON_ERROR: c_int = 4;
+
+pub const MPV_RENDER_PARAM_INVALID: c_int = 0;
+pub const MPV_RENDER_PARAM_API_TYPE: c_int = 1;
+pub const MPV_RENDER_PARAM_SW_SIZE: c_int = 17;
+pub const MPV_RENDER_PARAM_SW_FORMAT: c_int = 18;
+pub const MPV_RENDER_PARAM_SW_STRIDE: c_int = 19;
+pub const MPV_RENDER_PARAM_SW_POINTER: c_int = 20;
+
+pub const MPV_RENDER_API_TYPE_SW: &CStr = c"sw";
+pub const MPV_RENDER_UPDATE_FRAME: u64 = 1;
+
+#[link(name = "mpv")]
+unsafe extern "C" {
+    fn mpv_create() -> *mut mpv_handle;
+    fn mpv_initialize(ctx: *mut mpv_handle) -> c_int;
+    fn mpv_terminate_destroy(ctx: *mut mpv_handle);
+    fn mpv_error_string(error: c_int) -> *const c_char;
+    fn mpv_set_option_string(ctx: *mut mpv_handle, name: *const c_char, data: *const c_char) -> c_int;
+    fn mpv_command(ctx: *mut mpv_handle, args: *mut *const c_char) -> c_int;
+    fn mpv_set_property(ctx: *mut mpv_handle, name: *const c_char, format: c_int, data: *mut c_void) -> c_int;
+    fn mpv_set_property_string(ctx: *mut mpv_handle, name: *const c_char, data: *const c_char) -> c_int;
+    fn mpv_observe_property(ctx: *mut mpv_handle, reply_userdata: u64, name: *const c_char, format: c_int) -> c_int;
+    fn mpv_wait_event(ctx: *mut mpv_handle, timeout: f64) -> *mut mpv_event;
+    fn mpv_wakeup(ctx: *mut mpv_handle);
+
+    pub fn mpv_render_context_create(
+        res: *mut *mut mpv_render_context,
+        mpv: *mut mpv_handle,
+        params: *mut mpv_render_param,
+    ) -> c_int;
+    pub fn mpv_render_context_set_update_callback(
+        ctx: *mut mpv_render_context,
+        callback: Option<unsafe extern "C" fn(*mut c_void)>,
+        callback_ctx: *mut c_void,
+    );
+    pub fn mpv_render_context_update(ctx: *mut mpv_render_context) -> u64;
+    pub fn mpv_render_context_render(ctx: *mut mpv_render_context, params: *mut mpv_render_param) -> c_int;
+    pub fn mpv_render_context_free(ctx: *mut mpv_render_context);
+}
+
+fn check(code: c_int) -> Result<()> {
+    if code >= 0 {
+        return Ok(());
+    }
+    // SAFETY: mpv returns a static string for every error code.
+    let message = unsafe { CStr::from_ptr(mpv_error_string(code)) };
+    Err(anyhow!("mpv: {}", message.to_string_lossy()))
+}
+
+fn c_string(value: &str) -> Result<CString> {
+    CString::new(value).map_err(|_| anyhow!("mpv: argument contains a NUL byte"))
+}
+
+/// An mpv instance. The client API is thread-safe, so the handle is shared
+/// between the UI, the event thread and the render thread.
+pub struct Mpv {
+    handle: *mut mpv_handle,
+}
+
+// SAFETY: every mpv client function may be called from any thread.
+unsafe impl Send for Mpv {}
+unsafe impl Sync for Mpv {}
+
+impl Mpv {
+    /// Creates an instance, applying `options` before it starts.
+    pub fn new(options: &[(&str, &str)]) -> Result<Self> {
+        // SAFETY: plain constructor; null means out of memory.
+        let handle = unsafe { mpv_create() };
+        if handle.is_null() {
+            return Err(anyhow!("mpv: failed to create an instance"));
+        }
+        let mpv = Self { handle };
+        for (name, value) in options {
+            let (name, value) = (c_string(name)?, c_string(value)?);
+            // SAFETY: the handle is live and both strings outlive the call.
+            check(unsafe { mpv_set_option_string(mpv.handle, name.as_ptr(), value.as_ptr()) })?;
+        }
+        // SAFETY: the handle is live and not yet initialized.
+        check(unsafe { mpv_initialize(mpv.handle) })?;
+        Ok(mpv)
+    }
+
+    pub fn raw(&self) -> *mut mpv_handle {
+        self.handle
+    }
+
+    /// Runs a command such as `["seek", "10", "relative"]`.
+    pub fn command(&self, args: &[&str]) -> Result<()> {
+        let args = args.iter().map(|arg| c_string(arg)).collect::<Result<Vec<_>>>()?;
+        let mut pointers: Vec<*const c_char> = args.iter().map(|arg| arg.as_ptr()).collect();
+        pointers.push(ptr::null());
+        // SAFETY: a null-terminated array of strings that outlive the call.
+        check(unsafe { mpv_command(self.handle, pointers.as_mut_ptr()) })
+    }
+
+    pub fn set_flag(&self, name: &str, value: bool) -> Result<()> {
+        let name = c_string(name)?;
+        let mut value = c_int::from(value);
+        // SAFETY: MPV_FORMAT_FLAG takes a pointer to a C int.
+        check(unsafe { mpv_set_property(self.handle, name.as_ptr(), MPV_FORMAT_FLAG, (&raw mut value).cast()) })
+    }
+
+    pub fn set_double(&self, name: &str, value: f64) -> Result<()> {
+        let name = c_string(name)?;
+        let mut value = value;
+        // SAFETY: MPV_FORMAT_DOUBLE takes a pointer to a double.
+        check(unsafe { mpv_set_property(self.handle, name.as_ptr(), MPV_FORMAT_DOUBLE, (&raw mut value).cast()) })
+    }
+
+    pub fn set_string(&self, name: &str, value: &str) -> Result<()> {
+        let (name, value) = (c_string(name)?, c_string(value)?);
+        // SAFETY: mpv parses and copies the value.
+        check(unsafe { mpv_set_property_string(self.handle, name.as_ptr(), value.as_ptr()) })
+    }
+
+    /// Reports changes of `name` as property events tagged with `id`.
+    pub fn observe(&self, id: u64, name: &str, format: c_int) -> Result<()> {
+        let name = c_string(name)?;
+        // SAFETY: mpv copies the name.
+        check(unsafe { mpv_observe_property(self.handle, id, name.as_ptr(), format) })
+    }
+
+    /// Blocks until the next event. The returned event is only valid until
+    /// the next call, which is why it's handed to a closure.
+    pub fn wait_event<R>(&self, f: impl FnOnce(&mpv_event) -> R) -> R {
+        // SAFETY: mpv never returns null and keeps the event alive until the
+        // next wait on this handle, which can't happen inside `f`.
+        let event = unsafe { &*mpv_wait_event(self.handle, -1.) };
+        f(event)
+    }
+
+    /// Makes a blocked [`Self::wait_event`] return with an empty event.
+    pub fn wakeup(&self) {
+        // SAFETY: the handle is live.
+        unsafe { mpv_wakeup(self.handle) };
+    }
+}
+
+impl Drop for Mpv {
+    fn drop(&mut self) {
+        // SAFETY: the render context and the event thread are gone by the
+        // time the last reference to the instance is dropped.
+        unsafe { mpv_terminate_destroy(self.handle) };
+    }
+}
+
+/// The new value of an observed property. Most are unavailable while
+/// nothing is loaded.
+pub enum PropertyValue {
+    Flag(bool),
+    Double(f64),
+    Unavailable,
+}
+
+/// # Safety
+/// `event` must be a live `MPV_EVENT_PROPERTY_CHANGE` event.
+pub unsafe fn property_value(event: &mpv_event) -> PropertyValue {
+    // SAFETY: property change events carry an `mpv_event_property`, whose
+    // data matches its format.
+    unsafe {
+        let property = &*(event.data as *const mpv_event_property);
+        match property.format {
+            MPV_FORMAT_FLAG => PropertyValue::Flag(*(property.data as *const c_int) != 0),
+            MPV_FORMAT_DOUBLE => PropertyValue::Double(*(property.data as *const f64)),
+            _ => PropertyValue::Unavailable,
+        }
+    }
+}
+
+/// # Safety
+/// `event` must be a live `MPV_EVENT_END_FILE` event.
+pub unsafe fn end_file(event: &mpv_event) -> Result<bool> {
+    // SAFETY: end file events carry an `mpv_event_end_file`.
+    let end = unsafe { &*(event.data as *const mpv_event_end_file) };
+    match end.reason {
+        MPV_END_FILE_REASON_ERROR => check(end.error).map(|()| false),
+        reason => Ok(reason == MPV_END_FILE_REASON_EOF),
+    }
+}
@@ -0,0 +1,401 @@
+//! Deciding how an item reaches the player: the file as is, remuxed, or
+//! transcoded to HLS.
+//!
+//! The server answers a PlaybackInfo request with the item's media sources
+//! and what our [device profile](super::profile) allows for each. The choice
+//! itself is made by [`choose_stream`], which does no I/O so it can be
+//! checked against PlaybackInfo responses without a server.
+
+use super::profile::{device_profile, MAX_STREAMING_BITRATE};
+use crate::api::{
+    self, ApiError, GetPostedPlaybackInfoQuery, GetVideoStreamByContainerQuery, GetVideoStreamQuery, JellyfinClient,
+    MediaSourceInfo, PlayMethod, PlaybackErrorCode, PlaybackInfoDto, PlaybackInfoResponse,
+};
+use reqwest::Url;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PlaybackError {
+    #[error("This account isn't allowed to play this item")]
+    NotAllowed,
+
+    #[error("No version of this item can be played on this device")]
+    NoCompatibleStream,
+
+    #[error("Too many streams are playing on this account right now")]
+    RateLimitExceeded,
+
+    #[error(transparent)]
+    Api(#[from] ApiError),
+}
+
+/// What to ask the server for. Unset stream indices leave the pick to the
+/// server.
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackRequest {
+    pub user_id: Option<String>,
+    pub media_source_id: Option<String>,
+    pub audio_stream_index: Option<i32>,
+    pub subtitle_stream_index: Option<i32>,
+}
+
+impl PlaybackRequest {
+    fn to_dto(&self) -> PlaybackInfoDto {
+        PlaybackInfoDto {
+            user_id: self.user_id.clone(),
+            media_source_id: self.media_source_id.clone(),
+            audio_stream_index: self.audio_stream_index,
+            subtitle_stream_index: self.subtitle_stream_index,
+            max_streaming_bitrate: Some(MAX_STREAMING_BITRATE),
+            device_profile: Some(device_profile()),
+            enable_direct_play: Some(true),
+            enable_direct_stream: Some(true),
+            enable_transcoding: Some(true),
+            allow_video_stream_copy: Some(true),
+            allow_audio_stream_copy: Some(true),
+            auto_open_live_stream: Some(true),
+            ..Default::default()
+        }
+    }
+}
+
+/// How the chosen media source is delivered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamDelivery {
+    /// The file as stored.
+    DirectPlay,
+    /// The file's streams in the container the server names, copied rather
+    /// than re-encoded.
+    DirectStream { container: String },
+    /// The server's transcode, at the HLS playlist URL it gave.
+    Transcode { url: String },
+}
+
+/// The outcome of negotiating playback of one item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamChoice {
+    pub media_source: MediaSourceInfo,
+    pub delivery: StreamDelivery,
+    /// Identifies this playback in progress reports and transcode requests.
+    pub play_session_id: Option<String>,
+}
+
+impl StreamChoice {
+    pub fn play_method(&self) -> PlayMethod {
+        match self.delivery {
+            StreamDelivery::DirectPlay => PlayMethod::DirectPlay,
+            StreamDelivery::DirectStream { .. } => PlayMethod::DirectStream,
+            StreamDelivery::Transcode { .. } => PlayMethod::Transcode,
+        }
+    }
+
+    /// The subtitle stream the server burns into the picture, if any. Only a
+    /// transcode can have one.
+    pub fn burned_in_subtitle(&self) -> Option<i32> {
+        if !self.transcode_param("SubtitleMethod")?.eq_ignore_ascii_case("Encode") {
+            return None;
+        }
+        self.transcode_param("SubtitleStreamIndex")?.parse().ok()
+    }
+
+    /// Whether this stream plays the audio stream `audio_stream_index` with
+    /// `burned_in_subtitle` burned in, or needs replacing to. Played as is,
+    /// the file carries every embedded audio stream to pick from; a
+    /// transcode has only the one it was made with.
+    pub fn delivers(&self, audio_stream_index: Option<i32>, burned_in_subtitle: Option<i32>) -> bool {
+        if self.burned_in_subtitle() != burned_in_subtitle {
+            return false;
+        }
+        match self.delivery {
+            StreamDelivery::Transcode { .. } => {
+                let transcoded = self
+                    .transcode_param("AudioStreamIndex")
+                    .and_then(|index| index.parse().ok())
+                    .or(self.media_source.default_audio_stream_index);
+                audio_stream_index.is_none() || transcoded == audio_stream_index
+            }
+            _ => !audio_stream_index.is_some_and(|index| {
+                self.media_source
+                    .media_streams
+                    .iter()
+                    .flatten()
+                    .any(|stream| stream.index == Some(index) && stream.is_external == Some(true))
+            }),
+        }
+    }
+
+    /// A parameter of the transcode URL, which names what the server was
+    /// asked to make.
+    fn transcode_param(&self, name: &str) -> Option<&str> {
+        let StreamDelivery::Transcode { url } = &self.delivery else { return None };
+        let (_, query) = url.split_once('?')?;
+        query.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            key.eq_ignore_ascii_case(name).then_some(value)
+        })
+    }
+
+    /// Where the player fetches the stream from.
+    pub fn url(&self, client: &JellyfinClient, item_id: &str) -> api::Result<Url> {
+        let source = &self.media_source;
+        match &self.delivery {
+            StreamDelivery::DirectPlay => {
+                let query = GetVideoStreamQuery {
+                    static_: Some(true),
+                    media_source_id: source.id.clone(),
+                    device_id: Some(client.device().device_id.clone()),
+                    tag: source.e_tag.clone(),
+                    play_session_id: self.play_session_id.clone(),
+                    live_stream_id: source.live_stream_id.clone(),
+                    ..Default::default()
+                };
+                client.get_video_stream_url(item_id, &query)
+            }
+            StreamDelivery::DirectStream { container } => {
+                let query = GetVideoStreamByContainerQuery {
+                    static_: Some(true),
+                    media_source_id: source.id.clone(),
+                    device_id: Some(client.device().device_id.clone()),
+                    tag: source.e_tag.clone(),
+                    play_session_id: self.play_session_id.clone(),
+                    live_stream_id: source.live_stream_id.clone(),
+                    ..Default::default()
+                };
+                client.get_video_stream_by_container_url(item_id, container, &query)
+            }
+            // Already has every parameter the transcode needs.
+            StreamDelivery::Transcode { url } => client.endpoint_url(url),
+        }
+    }
+}
+
+/// Asks the server how `item_id` can be played here and picks the best way.
+pub async fn negotiate(
+    client: &JellyfinClient,
+    item_id: &str,
+    request: &PlaybackRequest,
+) -> Result<StreamChoice, PlaybackError> {
+    let query = GetPostedPlaybackInfoQuery {
+        user_id: request.user_id.clone(),
+        ..Default::default()
+    };
+    let response = client.get_posted_playback_info(item_id, &query, &request.to_dto()).await?;
+    choose_stream(&response, request.media_source_id.as_deref())
+}
+
+/// Picks a media source and how to deliver it from a PlaybackInfo response.
+///
+/// Sources are alternate versions of the item, e.g. 4K and 1080p, listed in
+/// the server's order of preference. Any source that plays as is beats one
+/// that needs remuxing, which beats one that needs transcoding, mirroring
+/// the official clients. With `media_source_id` only that version counts.
+pub fn choose_stream(
+    response: &PlaybackInfoResponse,
+    media_source_id: Option<&str>,
+) -> Result<StreamChoice, PlaybackError> {
+    match response.error_code {
+        None => {}
+        Some(PlaybackErrorCode::NotAllowed) => return Err(PlaybackError::NotAllowed),
+        Some(PlaybackErrorCode::RateLimitExceeded) => return Err(PlaybackError::RateLimitExceeded),
+        Some(PlaybackErrorCode::NoCompatibleStream | PlaybackErrorCode::Unrecognized) => {
+            return Err(PlaybackError::NoCompatibleStream);
+        }
+    }
+
+    let sources: Vec<&MediaSourceInfo> = response
+        .media_sources
+        .iter()
+        .flatten()
+        .filter(|source| media_source_id.is_none() || source.id.as_deref() == media_source_id)
+        .collect();
+
+    let direct_play = sources.iter().find(|source| source.supports_direct_play == Some(true));
+    let direct_stream = || {
+        sources.iter().find_map(|source| {
+            let container = source.container.as_deref().filter(|_| source.supports_direct_stream == Some(true))?;
+            // Probed containers can be a list of aliases, e.g. "mov,mp4,m4a".
+            let container = container.split(',').next().unwrap_or(container).to_string();
+            Some((*source, StreamDelivery::DirectStream { container }))
+        })
+    };
+    let transcode = || {
+        sources.iter().find_map(|source| {
+            let url = source.transcoding_url.clone().filter(|_| source.supports_transcoding == Some(true))?;
+            Some((*source, StreamDelivery::Transcode { url }))
+        })
+    };
+
+    let (source, delivery) = direct_play
+        .map(|source| (*source, StreamDelivery::DirectPlay))
+        .or_else(direct_stream)
+        .or_else(transcode)
+        .ok_or(PlaybackError::NoCompatibleStream)?;
+
+    Ok(StreamChoice {
+        media_source: source.clone(),
+        delivery,
+        play_session_id: response.play_session_id.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The fixtures are made by hand in the shape of PlaybackInfo responses,
+    // with made-up ids and mostly just the fields the choice looks at.
+
+    /// An MKV that plays as is.
+    const DIRECT_PLAY: &str = r#"{
+        "MediaSources": [{
+            "Protocol": "File",
+            "Id": "a1b2c3d4e5f60718293a4b5c6d7e8f90",
+            "Path": "/media/movies/Big Buck Bunny (2008)/Big Buck Bunny (2008).mkv",
+            "Type": "Default",
+            "Container": "mkv",
+            "Size": 276134947,
+            "Name": "Big Buck Bunny (2008)",
+            "IsRemote": false,
+            "ETag": "5e1f6a2b0c9d8e7f",
+            "RunTimeTicks": 5964800000,
+            "SupportsTranscoding": true,
+            "SupportsDirectStream": true,
+            "SupportsDirectPlay": true,
+            "IsInfiniteStream": false,
+            "RequiresOpening": false,
+            "RequiresClosing": false,
+            "SupportsProbing": true,
+            "MediaStreams": [
+                {"Codec": "h264", "Type": "Video", "Index": 0, "IsExternal": false},
+                {"Codec": "aac", "Language": "eng", "Type": "Audio", "Index": 1, "IsExternal": false}
+            ],
+            "Bitrate": 3704052,
+            "DefaultAudioStreamIndex": 1
+        }],
+        "PlaySessionId": "9f8e7d6c5b4a39281706f5e4d3c2b1a0"
+    }"#;
+
+    /// An MP4 whose audio needs remuxing; probed containers come as a list
+    /// of aliases.
+    const DIRECT_STREAM: &str = r#"{
+        "MediaSources": [{
+            "Protocol": "File",
+            "Id": "0f1e2d3c4b5a69788796a5b4c3d2e1f0",
+            "Container": "mov,mp4,m4a,3gp,3g2,mj2",
+            "Name": "Sintel (2010)",
+            "ETag": "8a7b6c5d4e3f2a1b",
+            "SupportsTranscoding": true,
+            "SupportsDirectStream": true,
+            "SupportsDirectPlay": false,
+            "MediaStreams": [
+                {"Codec": "h264", "Type": "Video", "Index": 0, "IsExternal": false},
+                {"Codec": "ac3", "Type": "Audio", "Index": 1, "IsExternal": false}
+            ],
+            "DefaultAudioStreamIndex": 1
+        }],
+        "PlaySessionId": "1a2b3c4d5e6f708192a3b4c5d6e7f809"
+    }"#;
+
+    /// A 4K version the server must transcode and a 1080p one that plays as
+    /// is, in the server's order.
+    const VERSIONS: &str = r#"{
+        "MediaSources": [
+            {
+                "Protocol": "File",
+                "Id": "4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b",
+                "Container": "mkv",
+                "Name": "2160p",
+                "SupportsTranscoding": true,
+                "SupportsDirectStream": false,
+                "SupportsDirectPlay": false,
+                "TranscodingUrl": "/videos/c0ffee00c0ffee00c0ffee00c0ffee00/master.m3u8?DeviceId=test&MediaSourceId=4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b&VideoCodec=h264&AudioCodec=aac&AudioStreamIndex=2&SubtitleStreamIndex=3&SubtitleMethod=Encode&PlaySessionId=5c5c5c5c5c5c5c5c5c5c5c5c5c5c5c5c&TranscodeReasons=VideoBitrateNotSupported",
+                "TranscodingSubProtocol": "hls",
+                "TranscodingContainer": "ts",
+                "DefaultAudioStreamIndex": 1
+            },
+            {
+                "Protocol": "File",
+                "Id": "1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a",
+                "Container": "mkv",
+                "Name": "1080p",
+                "SupportsTranscoding": true,
+                "SupportsDirectStream": true,
+                "SupportsDirectPlay": true,
+                "DefaultAudioStreamIndex": 1
+            }
+        ],
+        "PlaySessionId": "5c5c5c5c5c5c5c5c5c5c5c5c5c5c5c5c"
+    }"#;
+
+    fn response(json: &str) -> PlaybackInfoResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn error_response(code: &str) -> PlaybackInfoResponse {
+        response(&format!(r#"{{"MediaSources": [], "PlaySessionId": null, "ErrorCode": "{}"}}"#, code))
+    }
+
+    #[test]
+    fn picks_direct_play() {
+        let choice = choose_stream(&response(DIRECT_PLAY), None).unwrap();
+        assert_eq!(choice.delivery, StreamDelivery::DirectPlay);
+        assert_eq!(choice.play_method(), PlayMethod::DirectPlay);
+        assert_eq!(choice.media_source.id.as_deref(), Some("a1b2c3d4e5f60718293a4b5c6d7e8f90"));
+        assert_eq!(choice.play_session_id.as_deref(), Some("9f8e7d6c5b4a39281706f5e4d3c2b1a0"));
+    }
+
+    #[test]
+    fn picks_direct_stream_in_the_first_container_alias() {
+        let choice = choose_stream(&response(DIRECT_STREAM), None).unwrap();
+        assert_eq!(choice.delivery, StreamDelivery::DirectStream { container: "mov".to_string() });
+        assert_eq!(choice.play_method(), PlayMethod::DirectStream);
+    }
+
+    #[test]
+    fn prefers_a_version_that_plays_as_is() {
+        let choice = choose_stream(&response(VERSIONS), None).unwrap();
+        assert_eq!(choice.media_source.id.as_deref(), Some("1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a"));
+        assert_eq!(choice.delivery, StreamDelivery::DirectPlay);
+    }
+
+    #[test]
+    fn picks_transcode_of_the_requested_version() {
+        let choice = choose_stream(&response(VERSIONS), Some("4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b")).unwrap();
+        assert_eq!(choice.media_source.id.as_deref(), Some("4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b4b"));
+        assert_eq!(choice.play_method(), PlayMethod::Transcode);
+        let StreamDelivery::Transcode { url } = &choice.delivery else { panic!("not a transcode") };
+        assert!(url.starts_with("/videos/c0ffee00c0ffee00c0ffee00c0ffee00/master.m3u8?"));
+        assert_eq!(choice.burned_in_subtitle(), Some(3));
+        assert!(choice.delivers(Some(2), Some(3)));
+        assert!(!choice.delivers(Some(1), Some(3)));
+    }
+
+    #[test]
+    fn unknown_version_is_not_compatible() {
+        let result = choose_stream(&response(VERSIONS), Some("ffffffffffffffffffffffffffffffff"));
+        assert!(matches!(result, Err(PlaybackError::NoCompatibleStream)));
+    }
+
+    #[test]
+    fn maps_error_codes() {
+        assert!(matches!(
+            choose_stream(&error_response("NotAllowed"), None),
+            Err(PlaybackError::NotAllowed)
+        ));
+        assert!(matches!(
+            choose_stream(&error_response("NoCompatibleStream"), None),
+            Err(PlaybackError::NoCompatibleStream)
+        ));
+        assert!(matches!(
+            choose_stream(&error_response("RateLimitExceeded"), None),
+            Err(PlaybackError::RateLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn unrecognized_error_code_is_not_compatible() {
+        let response = error_response("SomethingNewer");
+        assert_eq!(response.error_code, Some(PlaybackErrorCode::Unrecognized));
+        assert!(matches!(choose_stream(&response, None), Err(PlaybackError::NoCompatibleStream)));
+    }
+}
@@ -0,0 +1,87 @@
+//! The device profile sent with PlaybackInfo requests, describing what the
+//! player takes as is so the server only transcodes what it has to.
+
+use crate::api::{
+    DeviceProfile, DirectPlayProfile, DlnaProfileType, EncodingContext, MediaStreamProtocol, SubtitleDeliveryMethod,
+    SubtitleProfile, TranscodingProfile,
+};
+
+/// Highest bitrate, in bits per second, played without transcoding. High
+/// enough for UHD remuxes on a local network.
+pub const MAX_STREAMING_BITRATE: i32 = 120_000_000;
+
+/// mpv decodes with FFmpeg in software, so anything FFmpeg knows plays.
+/// These are the names the server matches containers and codecs by.
+const VIDEO_CONTAINERS: &str = "mkv,webm,mp4,m4v,mov,avi,ts,mts,m2ts,mpegts,mpg,mpeg,vob,flv,wmv,asf,ogv,3gp";
+const VIDEO_CODECS: &str = "h264,hevc,av1,vp9,vp8,mpeg4,mpeg2video,mpeg1video,vc1,wmv3,msmpeg4v3,theora,h263";
+const AUDIO_CONTAINERS: &str = "mp3,aac,m4a,m4b,flac,alac,ogg,oga,opus,webma,wav,wma,mka,ape,wv";
+const AUDIO_CODECS: &str =
+    "aac,mp3,mp2,ac3,eac3,dts,truehd,mlp,flac,alac,opus,vorbis,wmav2,wmapro,pcm_s16le,pcm_s24le,pcm_s32le";
+
+const TEXT_SUBTITLES: &[&str] = &["srt", "subrip", "vtt", "webvtt", "ass", "ssa", "mov_text"];
+
+/// Everything the player plays as is, plus how the server should transcode
+/// what it doesn't: H.264 and AAC over HLS.
+pub fn device_profile() -> DeviceProfile {
+    DeviceProfile {
+        name: Some("Crabfin".to_string()),
+        max_streaming_bitrate: Some(MAX_STREAMING_BITRATE),
+        max_static_bitrate: Some(MAX_STREAMING_BITRATE),
+        direct_play_profiles: Some(vec![
+            DirectPlayProfile {
+                type_: Some(DlnaProfileType::Video),
+                container: Some(VIDEO_CONTAINERS.to_string()),
+                video_codec: Some(VIDEO_CODECS.to_string()),
+                audio_codec: Some(AUDIO_CODECS.to_string()),
+            },
+            DirectPlayProfile {
+                type_: Some(DlnaProfileType::Audio),
+                container: Some(AUDIO_CONTAINERS.to_string()),
+                audio_codec: Some(AUDIO_CODECS.to_string()),
+                video_codec: None,
+            },
+        ]),
+        transcoding_profiles: Some(vec![
+            TranscodingProfile {
+                type_: Some(DlnaProfileType::Video),
+                container: Some("ts".to_string()),
+                video_codec: Some("h264".to_string()),
+                audio_codec: Some("aac,mp3,ac3".to_string()),
+                protocol: Some(MediaStreamProtocol::Hls),
+                context: Some(EncodingContext::Streaming),
+                max_audio_channels: Some("6".to_string()),
+                min_segments: Some(1),
+                break_on_non_key_frames: Some(true),
+                ..Default::default()
+            },
+            TranscodingProfile {
+                type_: Some(DlnaProfileType::Audio),
+                container: Some("mp3".to_string()),
+                audio_codec: Some("mp3".to_string()),
+                protocol: Some(MediaStreamProtocol::Http),
+                context: Some(EncodingContext::Streaming),
+                max_audio_channels: Some("2".to_string()),
+                ..Default::default()
+            },
+        ]),
+        subtitle_profiles: Some(subtitle_profiles()),
+        ..Default::default()
+    }
+}
+
+/// Text subtitles are fetched separately and drawn by the player view, so
+/// they never stand in the way of direct play. Image subtitles are left out
+/// on purpose: when one is picked the server has to burn it in.
+fn subtitle_profiles() -> Vec<SubtitleProfile> {
+    let profile = |format: &str, method| SubtitleProfile {
+        format: Some(format.to_string()),
+        method: Some(method),
+        ..Default::default()
+    };
+    let mut profiles = Vec::new();
+    for format in TEXT_SUBTITLES {
+        profiles.push(profile(format, SubtitleDeliveryMethod::Embed));
+        profiles.push(profile(format, SubtitleDeliveryMethod::External));
+    }
+    profiles
+}
@@ -0,0 +1,469 @@
+//! Reporting playback to the server, which keeps watched state and resume
+//! points in step across the user's devices.
+//!
+//! Reports are sent in order by one task per session. Those that can't reach
+//! the server wait in a queue that's kept on disk, and go out before anything
+//! newer once the server answers again, even after a restart.
+
+use crate::api::{
+    ApiError, JellyfinClient, PlayMethod, PlaybackProgressInfo, PlaybackStartInfo, PlaybackStopInfo,
+};
+use crate::config::{write_atomic, Config};
+use anyhow::{Context as _, Result};
+use gpui::{App, AsyncApp};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// How often queued reports are retried while the server is unreachable.
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Jellyfin measures time in 100ns ticks.
+const TICKS_PER_SECOND: f64 = 10_000_000.;
+
+/// Where playback stands, which every kind of report is built from.
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackSnapshot {
+    pub item_id: String,
+    pub media_source_id: Option<String>,
+    pub live_stream_id: Option<String>,
+    pub play_session_id: Option<String>,
+    pub play_method: Option<PlayMethod>,
+    /// Seconds into the item.
+    pub position: f64,
+    pub audio_stream_index: Option<i32>,
+    pub subtitle_stream_index: Option<i32>,
+    pub is_paused: bool,
+    pub is_muted: bool,
+    /// Percent, 0 to 100.
+    pub volume: f64,
+}
+
+impl PlaybackSnapshot {
+    fn position_ticks(&self) -> i64 {
+        (self.position.max(0.) * TICKS_PER_SECOND) as i64
+    }
+
+    pub fn start(&self) -> Report {
+        Report::Start(PlaybackStartInfo {
+            item_id: Some(self.item_id.clone()),
+            media_source_id: self.media_source_id.clone(),
+            live_stream_id: self.live_stream_id.clone(),
+            play_session_id: self.play_session_id.clone(),
+            play_method: self.play_method,
+            position_ticks: Some(self.position_ticks()),
+            audio_stream_index: self.audio_stream_index,
+            subtitle_stream_index: self.subtitle_stream_index,
+            is_paused: Some(self.is_paused),
+            is_muted: Some(self.is_muted),
+            volume_level: Some(self.volume.round() as i32),
+            can_seek: Some(true),
+            ..Default::default()
+        })
+    }
+
+    pub fn progress(&self) -> Report {
+        Report::Progress(PlaybackProgressInfo {
+            item_id: Some(self.item_id.clone()),
+            media_source_id: self.media_source_id.clone(),
+            live_stream_id: self.live_stream_id.clone(),
+            play_session_id: self.play_session_id.clone(),
+            play_method: self.play_method,
+            position_ticks: Some(self.position_ticks()),
+            audio_stream_index: self.audio_stream_index,
+            subtitle_stream_index: self.subtitle_stream_index,
+            is_paused: Some(self.is_paused),
+            is_muted: Some(self.is_muted),
+            volume_level: Some(self.volume.round() as i32),
+            can_seek: Some(true),
+            ..Default::default()
+        })
+    }
+
+    /// `failed` tells the server playback ended in an error rather than by
+    /// the user's choice.
+    pub fn stopped(&self, failed: bool) -> Report {
+        Report::Stopped(PlaybackStopInfo {
+            item_id: Some(self.item_id.clone()),
+            media_source_id: self.media_source_id.clone(),
+            live_stream_id: self.live_stream_id.clone(),
+            play_session_id: self.play_session_id.clone(),
+            position_ticks: Some(self.position_ticks()),
+            failed: Some(failed),
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Report {
+    Start(PlaybackStartInfo),
+    Progress(PlaybackProgressInfo),
+    Stopped(PlaybackStopInfo),
+}
+
+impl Report {
+    fn play_session_id(&self) -> Option<&str> {
+        match self {
+            Report::Start(info) => info.play_session_id.as_deref(),
+            Report::Progress(info) => info.play_session_id.as_deref(),
+            Report::Stopped(info) => info.play_session_id.as_deref(),
+        }
+    }
+
+    async fn send(&self, client: &JellyfinClient) -> Result<(), ApiError> {
+        match self {
+            Report::Start(info) => client.report_playback_start(info).await,
+            Report::Progress(info) => client.report_playback_progress(info).await,
+            Report::Stopped(info) => client.report_playback_stopped(info).await,
+        }
+    }
+}
+
+/// A report waiting for its server, stored with whose it is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueuedReport {
+    server_id: String,
+    user_id: String,
+    report: Report,
+}
+
+/// Sends one user's playback reports to their server. Clones share the same
+/// queue; the sending task ends once every clone is gone.
+#[derive(Clone)]
+pub struct PlaybackReporter {
+    sender: UnboundedSender<Report>,
+}
+
+impl PlaybackReporter {
+    pub fn new(client: JellyfinClient, server_id: String, user_id: String, cx: &App) -> Self {
+        let (sender, receiver) = unbounded_channel();
+        let queue = ReportQueue::load(queue_path(), server_id, user_id);
+        cx.spawn(async move |cx| queue.run(client, receiver, cx).await).detach();
+        Self { sender }
+    }
+
+    pub fn report(&self, report: Report) {
+        let _ = self.sender.send(report);
+    }
+}
+
+struct ReportQueue {
+    /// The file every user's queued reports share.
+    path: PathBuf,
+    server_id: String,
+    user_id: String,
+    pending: VecDeque<Report>,
+    /// Whether the file holds reports of ours, so that it's left alone while
+    /// every report goes straight through.
+    stored: bool,
+}
+
+impl ReportQueue {
+    /// Picks up this user's reports left over from earlier runs.
+    fn load(path: PathBuf, server_id: String, user_id: String) -> Self {
+        let pending = match read_queue_file(&path) {
+            Ok(queued) => queued
+                .into_iter()
+                .filter(|queued| queued.server_id == server_id && queued.user_id == user_id)
+                .map(|queued| queued.report)
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Failed to read queued playback reports: {:#}", e);
+                VecDeque::new()
+            }
+        };
+        Self {
+            path,
+            server_id,
+            user_id,
+            stored: !pending.is_empty(),
+            pending,
+        }
+    }
+
+    async fn run(mut self, client: JellyfinClient, mut receiver: UnboundedReceiver<Report>, cx: &mut AsyncApp) {
+        self.flush(&client).await;
+        loop {
+            let report = if self.pending.is_empty() {
+                receiver.recv().await
+            } else {
+                let retry = cx.background_executor().timer(RETRY_INTERVAL);
+                tokio::select! {
+                    report = receiver.recv() => report,
+                    _ = retry => {
+                        self.flush(&client).await;
+                        continue;
+                    }
+                }
+            };
+            let Some(report) = report else { break };
+            self.push(report);
+            self.flush(&client).await;
+        }
+    }
+
+    /// Queues a report behind the others. Only the latest progress of a
+    /// playback matters, and none once it has stopped, so older ones go.
+    fn push(&mut self, report: Report) {
+        if matches!(report, Report::Progress(_) | Report::Stopped(_)) {
+            let session = report.play_session_id().map(str::to_string);
+            self.pending.retain(|queued| {
+                !(matches!(queued, Report::Progress(_)) && queued.play_session_id() == session.as_deref())
+            });
+        }
+        self.pending.push_back(report);
+    }
+
+    /// Sends queued reports oldest first, stopping at the first that can't
+    /// get through, such as while the server is unreachable or down behind
+    /// its proxy. Ones the server turns down are dropped, as sending them
+    /// again won't change its mind.
+    async fn flush(&mut self, client: &JellyfinClient) {
+        while let Some(report) = self.pending.front() {
+            match report.send(client).await {
+                Ok(()) => {}
+                Err(e) if e.is_temporary() => break,
+                Err(e) => tracing::warn!("The server rejected a playback report: {}", e),
+            }
+            self.pending.pop_front();
+        }
+        if self.pending.is_empty() && !self.stored {
+            return;
+        }
+        match self.save() {
+            Ok(()) => self.stored = !self.pending.is_empty(),
+            Err(e) => tracing::warn!("Failed to store queued playback reports: {:#}", e),
+        }
+    }
+
+    /// Writes this user's queue next to everyone else's.
+    fn save(&self) -> Result<()> {
+        let mut queued: Vec<QueuedReport> = read_queue_file(&self.path)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|queued| queued.server_id != self.server_id || queued.user_id != self.user_id)
+            .collect();
+        queued.extend(self.pending.iter().map(|report| QueuedReport {
+            server_id: self.server_id.clone(),
+            user_id: self.user_id.clone(),
+            report: report.clone(),
+        }));
+
+        if queued.is_empty() {
+            if self.path.exists() {
+                fs::remove_file(&self.path).context("Failed to remove the report queue")?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("Failed to create config directory")?;
+        }
+        let content = serde_json::to_string_pretty(&queued).context("Failed to serialize the report queue")?;
+        write_atomic(&self.path, content.as_bytes()).context("Failed to write the report queue")
+    }
+}
+
+/// Kept with the config rather than the cache: unlike cached data, the
+/// reports can't be fetched again.
+fn queue_path() -> PathBuf {
+    Config::config_dir().unwrap_or_default().join("playback_reports.json")
+}
+
+fn read_queue_file(path: &Path) -> Result<Vec<QueuedReport>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).context("Failed to read the report queue")?;
+    serde_json::from_str(&content).context("Failed to parse the report queue")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::DeviceInfo;
+    use std::net::Ipv4Addr;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A request the fake server got: its path and the report's session.
+    type Received = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+    fn test_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("crabfin-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("playback_reports.json")
+    }
+
+    fn client(url: &str) -> JellyfinClient {
+        let device = DeviceInfo::new("test-device");
+        JellyfinClient::new(JellyfinClient::http_client(&device).unwrap(), url, device).unwrap()
+    }
+
+    /// A client for a server that isn't running.
+    fn offline_client() -> JellyfinClient {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        client(&url)
+    }
+
+    /// Answers one request with each of `statuses` in turn.
+    async fn serve(statuses: Vec<u16>) -> (JellyfinClient, Received) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let received = Received::default();
+        let log = received.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let (path, body) = read_request(&mut stream).await;
+                let session = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["PlaySessionId"]
+                    .as_str()
+                    .map(str::to_string);
+                log.lock().unwrap().push((path, session));
+                let response = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (client(&url), received)
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> (String, Vec<u8>) {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let len = stream.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..len]);
+            let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") else { continue };
+            let head = String::from_utf8_lossy(&data[..end]).to_string();
+            let length = head
+                .lines()
+                .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:")?.trim().parse().ok())
+                .unwrap_or(0);
+            if data.len() >= end + 4 + length {
+                let path = head.split(' ').nth(1).unwrap_or_default().to_string();
+                return (path, data[end + 4..end + 4 + length].to_vec());
+            }
+        }
+    }
+
+    fn snapshot(session: &str) -> PlaybackSnapshot {
+        PlaybackSnapshot {
+            item_id: "item".to_string(),
+            play_session_id: Some(session.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn load(path: &Path, user_id: &str) -> ReportQueue {
+        ReportQueue::load(path.to_path_buf(), "server".to_string(), user_id.to_string())
+    }
+
+    #[tokio::test]
+    async fn queues_while_offline() {
+        let path = test_path("reports-offline");
+        let mut queue = load(&path, "user");
+        queue.push(snapshot("a").start());
+        queue.push(snapshot("a").progress());
+        queue.flush(&offline_client()).await;
+        assert_eq!(queue.pending.len(), 2);
+
+        // Still there after a restart, for this user only.
+        let reloaded = load(&path, "user");
+        assert_eq!(reloaded.pending, queue.pending);
+        assert!(load(&path, "someone else").pending.is_empty());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sends_in_order_once_back() {
+        let path = test_path("reports-order");
+        let mut queue = load(&path, "user");
+        queue.push(snapshot("a").start());
+        queue.push(snapshot("a").progress());
+        queue.flush(&offline_client()).await;
+
+        // Reports from the last run go out before newer ones.
+        let (client, received) = serve(vec![204; 3]).await;
+        let mut queue = load(&path, "user");
+        queue.push(snapshot("b").start());
+        queue.flush(&client).await;
+        assert!(queue.pending.is_empty());
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                ("/Sessions/Playing".to_string(), Some("a".to_string())),
+                ("/Sessions/Playing/Progress".to_string(), Some("a".to_string())),
+                ("/Sessions/Playing".to_string(), Some("b".to_string())),
+            ]
+        );
+        assert!(!path.exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn stopping_drops_queued_progress() {
+        let mut queue = load(&test_path("reports-stopped"), "user");
+        queue.push(snapshot("a").start());
+        queue.push(snapshot("a").progress());
+        queue.push(snapshot("b").progress());
+        queue.push(snapshot("a").stopped(false));
+        assert_eq!(
+            queue.pending,
+            [snapshot("a").start(), snapshot("b").progress(), snapshot("a").stopped(false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn drops_reports_the_server_rejects() {
+        let path = test_path("reports-rejected");
+        let (client, received) = serve(vec![400, 204]).await;
+        let mut queue = load(&path, "user");
+        queue.push(snapshot("a").start());
+        queue.push(snapshot("b").start());
+        queue.flush(&client).await;
+        assert!(queue.pending.is_empty());
+        assert_eq!(received.lock().unwrap().len(), 2);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn keeps_reports_while_the_server_fails() {
+        let path = test_path("reports-server-error");
+        let (client, received) = serve(vec![502]).await;
+        let mut queue = load(&path, "user");
+        queue.push(snapshot("a").start());
+        queue.push(snapshot("b").start());
+        queue.flush(&client).await;
+        assert_eq!(queue.pending, [snapshot("a").start(), snapshot("b").start()]);
+        assert_eq!(received.lock().unwrap().len(), 1);
+        assert_eq!(load(&path, "user").pending, queue.pending);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn users_share_the_file() {
+        let path = test_path("reports-users");
+        let mut first = load(&path, "first");
+        first.push(snapshot("a").start());
+        first.flush(&offline_client()).await;
+        let mut second = load(&path, "second");
+        second.push(snapshot("b").progress());
+        second.flush(&offline_client()).await;
+
+        assert_eq!(load(&path, "first").pending, [snapshot("a").start()]);
+        assert_eq!(load(&path, "second").pending, [snapshot("b").progress()]);
+
+        // Sending one user's reports leaves the other's stored.
+        let (client, _) = serve(vec![204]).await;
+        first.flush(&client).await;
+        assert!(load(&path, "first").pending.is_empty());
+        assert_eq!(load(&path, "second").pending, [snapshot("b").progress()]);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
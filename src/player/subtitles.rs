@@ -0,0 +1,365 @@
+//! Subtitles drawn by the player view on top of the video.
+//!
+//! mpv never shows subtitles itself. Text subtitles, whether embedded or
+//! external, are fetched from the server and drawn with the user's
+//! [style](crate::config::SubtitleStyle). Image subtitles can't be drawn
+//! that way, so the server burns them into a transcode instead.
+
+use crate::api::{GetSubtitleQuery, JellyfinClient, MediaSourceInfo, MediaStream, MediaStreamType};
+use anyhow::{Context as _, Result};
+
+/// A subtitle stream of a media source, as offered in the track menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleTrack {
+    /// The stream's index among all of the source's streams.
+    pub index: i32,
+    pub label: String,
+    pub language: Option<String>,
+    pub is_default: bool,
+    pub is_forced: bool,
+    /// A separate file next to the video rather than part of it.
+    pub is_external: bool,
+    /// Text subtitles are drawn here; the rest need burning in.
+    pub is_text: bool,
+    codec: Option<String>,
+}
+
+impl SubtitleTrack {
+    fn from_stream(stream: &MediaStream) -> Option<Self> {
+        if stream.type_ != Some(MediaStreamType::Subtitle) {
+            return None;
+        }
+        let index = stream.index?;
+        let label = stream
+            .display_title
+            .clone()
+            .or_else(|| stream.title.clone())
+            .or_else(|| stream.language.clone())
+            .unwrap_or_else(|| format!("Track {}", index));
+        Some(Self {
+            index,
+            label,
+            language: stream.language.clone(),
+            is_default: stream.is_default == Some(true),
+            is_forced: stream.is_forced == Some(true),
+            is_external: stream.is_external == Some(true),
+            is_text: stream.is_text_subtitle_stream == Some(true),
+            codec: stream.codec.clone(),
+        })
+    }
+
+    /// What to fetch the track as. ASS and WebVTT come as they are; the
+    /// server converts every other text format to SubRip.
+    fn format(&self) -> SubtitleFormat {
+        match self.codec.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            Some("ass" | "ssa") => SubtitleFormat::Ass,
+            Some("vtt" | "webvtt") => SubtitleFormat::Vtt,
+            _ => SubtitleFormat::Srt,
+        }
+    }
+}
+
+/// The source's subtitle streams, embedded ones first as the server lists
+/// them.
+pub fn subtitle_tracks(source: &MediaSourceInfo) -> Vec<SubtitleTrack> {
+    source
+        .media_streams
+        .iter()
+        .flatten()
+        .filter_map(SubtitleTrack::from_stream)
+        .collect()
+}
+
+/// The stream the server has to burn in for the track at `index` to show,
+/// which is the track itself when it's an image one.
+pub fn burn_in(tracks: &[SubtitleTrack], index: Option<i32>) -> Option<i32> {
+    let index = index?;
+    tracks.iter().any(|track| track.index == index && !track.is_text).then_some(index)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubtitleFormat {
+    Srt,
+    Vtt,
+    Ass,
+}
+
+impl SubtitleFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+            SubtitleFormat::Ass => "ass",
+        }
+    }
+}
+
+/// One line or group of lines, shown from `start` until `end` seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// A track's cues, ordered by when they start.
+#[derive(Debug, Clone, Default)]
+pub struct Subtitles {
+    cues: Vec<Cue>,
+}
+
+impl Subtitles {
+    fn parse(format: SubtitleFormat, content: &str) -> Result<Self> {
+        let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+        let mut cues = match format {
+            SubtitleFormat::Srt | SubtitleFormat::Vtt => parse_blocks(&content),
+            SubtitleFormat::Ass => parse_ass(&content)?,
+        };
+        cues.retain(|cue| cue.end > cue.start && !cue.text.trim().is_empty());
+        cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Ok(Self { cues })
+    }
+
+    /// The cues showing at `time`, in the order they started.
+    pub fn at(&self, time: f64) -> impl Iterator<Item = &Cue> {
+        let started = self.cues.partition_point(|cue| cue.start <= time);
+        self.cues[..started].iter().filter(move |cue| cue.end > time)
+    }
+}
+
+/// Downloads a text track of `media_source_id` and parses it.
+pub async fn fetch_subtitles(
+    client: &JellyfinClient,
+    item_id: &str,
+    media_source_id: &str,
+    track: &SubtitleTrack,
+) -> Result<Subtitles> {
+    let format = track.format();
+    let bytes = client
+        .get_subtitle(item_id, media_source_id, track.index, format.extension(), &GetSubtitleQuery::default())
+        .await?;
+    Subtitles::parse(format, &String::from_utf8_lossy(&bytes))
+        .with_context(|| format!("Failed to read subtitle track {}", track.index))
+}
+
+/// SubRip and WebVTT: blocks separated by blank lines, each with a
+/// `start --> end` line followed by the text. Blocks without one, like the
+/// WebVTT header, notes and styles, are skipped.
+fn parse_blocks(content: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    for block in content.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(timing) = lines.next() else { continue };
+        let Some((start, rest)) = timing.split_once("-->") else { continue };
+        // WebVTT cue settings follow the end time.
+        let end = rest.split_whitespace().next().unwrap_or_default();
+        let (Some(start), Some(end)) = (parse_timestamp(start.trim()), parse_timestamp(end)) else {
+            continue;
+        };
+        let text = lines.map(strip_markup).collect::<Vec<_>>().join("\n");
+        cues.push(Cue { start, end, text });
+    }
+    cues
+}
+
+/// "01:02:03,456", "01:02:03.456", "02:03.456" or ASS's "1:02:03.45".
+fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let mut seconds = 0.;
+    for part in timestamp.split(':') {
+        let value: f64 = part.trim().replace(',', ".").parse().ok()?;
+        seconds = seconds * 60. + value;
+    }
+    Some(seconds)
+}
+
+/// Drops formatting tags such as `<i>` or `<font color=...>` and decodes
+/// the entities WebVTT escapes text with.
+fn strip_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+/// Advanced SubStation Alpha: the `Dialogue` lines of the `[Events]`
+/// section, read in the order its `Format` line gives. Only the text is
+/// kept; positioning and styles give way to the user's style.
+fn parse_ass(content: &str) -> Result<Vec<Cue>> {
+    let mut in_events = false;
+    let mut format: Option<Vec<String>> = None;
+    let mut cues = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = Some(fields.split(',').map(|field| field.trim().to_ascii_lowercase()).collect());
+            continue;
+        }
+        let Some(values) = line.strip_prefix("Dialogue:") else { continue };
+        let fields = format.as_ref().context("Dialogue before the Format line")?;
+        let field = |name: &str| fields.iter().position(|field| field == name);
+        let (Some(start), Some(end), Some(text)) = (field("start"), field("end"), field("text")) else {
+            anyhow::bail!("The Format line lacks Start, End or Text");
+        };
+        // The text is last and may contain commas of its own.
+        let values: Vec<&str> = values.splitn(fields.len(), ',').collect();
+        let value = |index: usize| values.get(index).copied().unwrap_or_default();
+        let (Some(start), Some(end)) = (parse_timestamp(value(start)), parse_timestamp(value(end))) else {
+            continue;
+        };
+        cues.push(Cue {
+            start,
+            end,
+            text: strip_ass_overrides(value(text)),
+        });
+    }
+    Ok(cues)
+}
+
+/// Removes `{...}` override blocks and vector drawings, and turns ASS's
+/// escapes into plain characters.
+fn strip_ass_overrides(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut drawing = false;
+    while let Some(open) = rest.find('{') {
+        if !drawing {
+            plain.push_str(&rest[..open]);
+        }
+        let Some(close) = rest[open..].find('}') else {
+            rest = "";
+            break;
+        };
+        let block = &rest[open + 1..open + close];
+        // `\p1` and up start a drawing in place of text, `\p0` ends it.
+        let level = block
+            .split('\\')
+            .rev()
+            .find_map(|tag| tag.strip_prefix('p')?.trim().parse::<u32>().ok());
+        if let Some(level) = level {
+            drawing = level > 0;
+        }
+        rest = &rest[open + close + 1..];
+    }
+    if !drawing {
+        plain.push_str(rest);
+    }
+    plain.replace("\\N", "\n").replace("\\n", "\n").replace("\\h", "\u{a0}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts_at(subtitles: &Subtitles, time: f64) -> Vec<&str> {
+        subtitles.at(time).map(|cue| cue.text.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_srt() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>Hello</i> there\r\n\r\n\
+                   2\r\n00:01:02,250 --> 00:01:04,000\r\nTwo\r\nlines\r\n";
+        let subtitles = Subtitles::parse(SubtitleFormat::Srt, srt).unwrap();
+        assert_eq!(
+            subtitles.cues,
+            [
+                Cue { start: 1., end: 2.5, text: "Hello there".to_string() },
+                Cue { start: 62.25, end: 64., text: "Two\nlines".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_vtt() {
+        let vtt = "WEBVTT - Some title\n\n\
+                   NOTE made by hand\n\n\
+                   STYLE\n::cue { color: yellow }\n\n\
+                   intro\n00:01.000 --> 00:02.000 align:start position:10%\n<v Roger>Tom &amp; Jerry &lt;3</v>\n\n\
+                   01:00:00.500 --> 01:00:01.000\nLate\n";
+        let subtitles = Subtitles::parse(SubtitleFormat::Vtt, vtt).unwrap();
+        assert_eq!(
+            subtitles.cues,
+            [
+                Cue { start: 1., end: 2., text: "Tom & Jerry <3".to_string() },
+                Cue { start: 3600.5, end: 3601., text: "Late".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("01:02:03,456"), Some(3723.456));
+        assert_eq!(parse_timestamp("01:02:03.456"), Some(3723.456));
+        assert_eq!(parse_timestamp("02:03.456"), Some(123.456));
+        assert_eq!(parse_timestamp("1:02:03.45"), Some(3723.45));
+        assert_eq!(parse_timestamp("soon"), None);
+    }
+
+    #[test]
+    fn parses_ass_in_format_order() {
+        let ass = "[Script Info]\nTitle: Test\n\n\
+                   [V4+ Styles]\nFormat: Name, Fontname\nStyle: Default,Arial\n\n\
+                   [Events]\n\
+                   Format: Layer, Style, Start, End, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Dialogue: 0,Default,0:00:01.00,0:00:03.50,,0,0,0,,Well, well,\\Nwell.\n\
+                   Comment: 0,Default,0:00:02.00,0:00:03.00,,0,0,0,,Not shown\n";
+        let subtitles = Subtitles::parse(SubtitleFormat::Ass, ass).unwrap();
+        assert_eq!(
+            subtitles.cues,
+            [Cue { start: 1., end: 3.5, text: "Well, well,\nwell.".to_string() }]
+        );
+
+        // Text is only last when the Format line says so.
+        let ass = "[Events]\nFormat: Start, End, Style, Text\nDialogue: 0:00:05.00,0:00:06.00,Default,A, B\n";
+        let subtitles = Subtitles::parse(SubtitleFormat::Ass, ass).unwrap();
+        assert_eq!(subtitles.cues, [Cue { start: 5., end: 6., text: "A, B".to_string() }]);
+    }
+
+    #[test]
+    fn ass_needs_a_format_line() {
+        assert!(Subtitles::parse(SubtitleFormat::Ass, "[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Hi\n").is_err());
+        assert!(Subtitles::parse(SubtitleFormat::Ass, "[Events]\nFormat: Start, End\nDialogue: 0:00:01.00,0:00:02.00\n").is_err());
+    }
+
+    #[test]
+    fn strips_ass_overrides() {
+        assert_eq!(strip_ass_overrides("{\\an8\\fs40}Top{\\b1}bold{\\b0}"), "Topbold");
+        assert_eq!(strip_ass_overrides("Line one\\Nline two\\nthree"), "Line one\nline two\nthree");
+        assert_eq!(strip_ass_overrides("no\\hbreak"), "no\u{a0}break");
+        assert_eq!(strip_ass_overrides("Before{\\p1}m 0 0 l 10 10{\\p0}after"), "Beforeafter");
+        assert_eq!(strip_ass_overrides("Cut {\\i1"), "Cut ");
+    }
+
+    #[test]
+    fn returns_overlapping_cues() {
+        let srt = "1\n00:00:01,000 --> 00:00:05,000\nLong\n\n\
+                   2\n00:00:02,000 --> 00:00:03,000\nShort\n\n\
+                   3\n00:00:04,000 --> 00:00:04,000\nEmpty span\n\n\
+                   4\n00:00:06,000 --> 00:00:07,000\nLater\n";
+        let subtitles = Subtitles::parse(SubtitleFormat::Srt, srt).unwrap();
+        assert_eq!(texts_at(&subtitles, 0.5), Vec::<&str>::new());
+        assert_eq!(texts_at(&subtitles, 2.5), ["Long", "Short"]);
+        // A cue ends at its end time and the next starts at its start time.
+        assert_eq!(texts_at(&subtitles, 3.), ["Long"]);
+        assert_eq!(texts_at(&subtitles, 4.), ["Long"]);
+        assert_eq!(texts_at(&subtitles, 5.5), Vec::<&str>::new());
+        assert_eq!(texts_at(&subtitles, 6.), ["Later"]);
+    }
+}
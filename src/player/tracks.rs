@@ -0,0 +1,340 @@
+//! Audio tracks, and which audio and subtitle streams to start with.
+//!
+//! The defaults follow the language preferences in the user's server
+//! account, the same way the server and the official clients pick them, so
+//! an item starts with the same tracks on every device.
+
+use super::subtitles::SubtitleTrack;
+use crate::api::{MediaSourceInfo, MediaStream, MediaStreamType, SubtitlePlaybackMode, UserDto};
+
+/// An audio stream of a media source, as offered in the track menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioTrack {
+    /// The stream's index among all of the source's streams.
+    pub index: i32,
+    pub label: String,
+    pub language: Option<String>,
+    pub is_default: bool,
+    /// A separate file next to the video rather than part of it.
+    pub is_external: bool,
+}
+
+impl AudioTrack {
+    fn from_stream(stream: &MediaStream) -> Option<Self> {
+        if stream.type_ != Some(MediaStreamType::Audio) {
+            return None;
+        }
+        let index = stream.index?;
+        let label = stream
+            .display_title
+            .clone()
+            .or_else(|| stream.title.clone())
+            .or_else(|| stream.language.clone())
+            .unwrap_or_else(|| format!("Track {}", index));
+        Some(Self {
+            index,
+            label,
+            language: stream.language.clone(),
+            is_default: stream.is_default == Some(true),
+            is_external: stream.is_external == Some(true),
+        })
+    }
+}
+
+/// The source's audio streams in the server's order.
+pub fn audio_tracks(source: &MediaSourceInfo) -> Vec<AudioTrack> {
+    source
+        .media_streams
+        .iter()
+        .flatten()
+        .filter_map(AudioTrack::from_stream)
+        .collect()
+}
+
+/// mpv numbers a file's audio tracks from 1 in the order they're stored,
+/// which is how the server lists the embedded ones. External tracks aren't
+/// in the file, so they have no number.
+pub fn mpv_audio_id(tracks: &[AudioTrack], index: i32) -> Option<i64> {
+    let position = tracks
+        .iter()
+        .filter(|track| !track.is_external)
+        .position(|track| track.index == index)?;
+    Some(position as i64 + 1)
+}
+
+/// What the user set under Playback and Subtitles in their account.
+#[derive(Debug, Clone)]
+pub struct LanguagePreferences {
+    /// ISO 639-2 codes, e.g. "eng", as the server tags streams.
+    pub audio_language: Option<String>,
+    /// Whether the file's default audio track wins over the language.
+    pub play_default_audio_track: bool,
+    pub subtitle_language: Option<String>,
+    pub subtitle_mode: SubtitlePlaybackMode,
+}
+
+/// The server's defaults for a new account.
+impl Default for LanguagePreferences {
+    fn default() -> Self {
+        Self {
+            audio_language: None,
+            play_default_audio_track: true,
+            subtitle_language: None,
+            subtitle_mode: SubtitlePlaybackMode::Default,
+        }
+    }
+}
+
+impl LanguagePreferences {
+    pub fn from_user(user: &UserDto) -> Self {
+        let Some(config) = &user.configuration else { return Self::default() };
+        // The settings page stores "Any language" as an empty string.
+        let language = |value: &Option<String>| value.clone().filter(|language| !language.is_empty());
+        Self {
+            audio_language: language(&config.audio_language_preference),
+            play_default_audio_track: config.play_default_audio_track.unwrap_or(true),
+            subtitle_language: language(&config.subtitle_language_preference),
+            subtitle_mode: config.subtitle_mode.unwrap_or(SubtitlePlaybackMode::Default),
+        }
+    }
+
+    /// The audio stream to start with: the file's default when the user
+    /// asked for that, otherwise the first in their language, otherwise
+    /// the first one.
+    pub fn audio_stream(&self, tracks: &[AudioTrack]) -> Option<i32> {
+        let default = || tracks.iter().find(|track| track.is_default);
+        let preferred = || tracks.iter().find(|track| is_language(&track.language, &self.audio_language));
+        let track = if self.play_default_audio_track {
+            default().or_else(preferred)
+        } else {
+            preferred().or_else(default)
+        };
+        track.or(tracks.first()).map(|track| track.index)
+    }
+
+    /// The subtitle stream to start with, if any, for the audio in
+    /// `audio_language`. Mirrors the server's subtitle modes:
+    ///
+    /// - Default: whatever the file flags as default or forced.
+    /// - Smart: the user's language when the audio is in another one,
+    ///   otherwise only forced subtitles.
+    /// - Always: the user's language whenever it's there.
+    /// - OnlyForced: forced subtitles only.
+    /// - None: no subtitles.
+    pub fn subtitle_stream(&self, tracks: &[SubtitleTrack], audio_language: Option<&str>) -> Option<i32> {
+        // Ordered the way the server ranks candidates.
+        let mut ranked: Vec<&SubtitleTrack> = tracks.iter().collect();
+        ranked.sort_by_key(|track| {
+            let forced_for_audio =
+                track.is_forced && track.language.as_deref().zip(audio_language).is_some_and(same_language);
+            (
+                !track.is_external,
+                !forced_for_audio,
+                !track.is_forced,
+                !track.is_default,
+                !is_language(&track.language, &self.subtitle_language),
+            )
+        });
+
+        let flagged = || ranked.iter().find(|track| track.is_external || track.is_default || track.is_forced);
+        let forced = || ranked.iter().find(|track| track.is_forced);
+        // Full subtitles in the user's language, or forced ones if that's all.
+        let preferred = || {
+            let in_language = |track: &&&SubtitleTrack| is_language(&track.language, &self.subtitle_language);
+            let mut candidates = ranked.iter().filter(in_language);
+            let first = candidates.clone().next();
+            candidates.find(|track| !track.is_forced).or(first)
+        };
+
+        let track = match self.subtitle_mode {
+            SubtitlePlaybackMode::None => None,
+            SubtitlePlaybackMode::OnlyForced => forced(),
+            SubtitlePlaybackMode::Always => preferred().or_else(flagged),
+            SubtitlePlaybackMode::Smart => {
+                let audio_is_preferred = audio_language.is_some_and(|audio| {
+                    self.subtitle_language.as_deref().is_some_and(|preferred| same_language((preferred, audio)))
+                });
+                if audio_is_preferred { forced() } else { preferred().or_else(flagged) }
+            }
+            SubtitlePlaybackMode::Default | SubtitlePlaybackMode::Unrecognized => flagged(),
+        };
+        track.map(|track| track.index)
+    }
+}
+
+/// Whether a stream tagged `language` is in the `preferred` one. Without a
+/// preference nothing matches.
+fn is_language(language: &Option<String>, preferred: &Option<String>) -> bool {
+    language.as_deref().zip(preferred.as_deref()).is_some_and(same_language)
+}
+
+fn same_language((a, b): (&str, &str)) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::subtitles::subtitle_tracks;
+
+    #[derive(Default)]
+    struct Flags {
+        default: bool,
+        forced: bool,
+        external: bool,
+    }
+
+    fn stream(index: i32, type_: MediaStreamType, language: &str, flags: Flags) -> MediaStream {
+        MediaStream {
+            index: Some(index),
+            type_: Some(type_),
+            language: Some(language.to_string()),
+            is_default: Some(flags.default),
+            is_forced: Some(flags.forced),
+            is_external: Some(flags.external),
+            is_text_subtitle_stream: Some(type_ == MediaStreamType::Subtitle),
+            ..Default::default()
+        }
+    }
+
+    fn source(streams: Vec<MediaStream>) -> MediaSourceInfo {
+        MediaSourceInfo {
+            media_streams: Some(streams),
+            ..Default::default()
+        }
+    }
+
+    /// Japanese audio flagged default, English audio, and an external
+    /// French track, with a subtitle stream between the embedded ones.
+    fn audio_source() -> MediaSourceInfo {
+        let default = Flags { default: true, ..Default::default() };
+        let external = Flags { external: true, ..Default::default() };
+        source(vec![
+            stream(0, MediaStreamType::Video, "und", Flags::default()),
+            stream(1, MediaStreamType::Audio, "jpn", default),
+            stream(2, MediaStreamType::Subtitle, "eng", Flags::default()),
+            stream(3, MediaStreamType::Audio, "eng", Flags::default()),
+            stream(4, MediaStreamType::Audio, "fre", external),
+        ])
+    }
+
+    /// Full English subtitles, forced English ones and Spanish ones flagged
+    /// default.
+    fn subtitle_source() -> MediaSourceInfo {
+        let forced = Flags { forced: true, ..Default::default() };
+        let default = Flags { default: true, ..Default::default() };
+        source(vec![
+            stream(0, MediaStreamType::Video, "und", Flags::default()),
+            stream(1, MediaStreamType::Audio, "eng", Flags::default()),
+            stream(2, MediaStreamType::Subtitle, "eng", Flags::default()),
+            stream(3, MediaStreamType::Subtitle, "eng", forced),
+            stream(4, MediaStreamType::Subtitle, "spa", default),
+        ])
+    }
+
+    fn audio_preferences(language: Option<&str>, play_default_audio_track: bool) -> LanguagePreferences {
+        LanguagePreferences {
+            audio_language: language.map(str::to_string),
+            play_default_audio_track,
+            ..Default::default()
+        }
+    }
+
+    fn subtitle_preferences(mode: SubtitlePlaybackMode) -> LanguagePreferences {
+        LanguagePreferences {
+            subtitle_language: Some("eng".to_string()),
+            subtitle_mode: mode,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn picks_audio_by_language() {
+        let tracks = audio_tracks(&audio_source());
+        assert_eq!(audio_preferences(Some("eng"), false).audio_stream(&tracks), Some(3));
+        assert_eq!(audio_preferences(Some("ENG"), false).audio_stream(&tracks), Some(3));
+        // A missing language falls back to the default track.
+        assert_eq!(audio_preferences(Some("ger"), false).audio_stream(&tracks), Some(1));
+        assert_eq!(audio_preferences(None, false).audio_stream(&tracks), Some(1));
+    }
+
+    #[test]
+    fn default_audio_wins_when_asked_to() {
+        let tracks = audio_tracks(&audio_source());
+        assert_eq!(audio_preferences(Some("eng"), true).audio_stream(&tracks), Some(1));
+
+        // Without a default track, the language still counts.
+        let mut tracks = tracks;
+        tracks.iter_mut().for_each(|track| track.is_default = false);
+        assert_eq!(audio_preferences(Some("eng"), true).audio_stream(&tracks), Some(3));
+        assert_eq!(audio_preferences(Some("ger"), true).audio_stream(&tracks), Some(1));
+        assert_eq!(audio_preferences(None, true).audio_stream(&[]), None);
+    }
+
+    #[test]
+    fn numbers_audio_tracks_like_mpv() {
+        let tracks = audio_tracks(&audio_source());
+        assert_eq!(mpv_audio_id(&tracks, 1), Some(1));
+        assert_eq!(mpv_audio_id(&tracks, 3), Some(2));
+        // Not in the file, and not an audio stream.
+        assert_eq!(mpv_audio_id(&tracks, 4), None);
+        assert_eq!(mpv_audio_id(&tracks, 2), None);
+    }
+
+    #[test]
+    fn default_mode_takes_flagged_subtitles() {
+        let tracks = subtitle_tracks(&subtitle_source());
+        let preferences = subtitle_preferences(SubtitlePlaybackMode::Default);
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("eng")), Some(3));
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("jpn")), Some(3));
+
+        let unforced: Vec<_> = tracks.into_iter().filter(|track| !track.is_forced).collect();
+        assert_eq!(preferences.subtitle_stream(&unforced, Some("eng")), Some(4));
+    }
+
+    #[test]
+    fn always_mode_takes_full_subtitles_in_the_language() {
+        let tracks = subtitle_tracks(&subtitle_source());
+        let preferences = subtitle_preferences(SubtitlePlaybackMode::Always);
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("eng")), Some(2));
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("jpn")), Some(2));
+
+        let other_language = LanguagePreferences {
+            subtitle_language: Some("ger".to_string()),
+            ..preferences
+        };
+        assert_eq!(other_language.subtitle_stream(&tracks, Some("jpn")), Some(3));
+    }
+
+    #[test]
+    fn only_forced_mode_takes_forced_subtitles() {
+        let tracks = subtitle_tracks(&subtitle_source());
+        let preferences = subtitle_preferences(SubtitlePlaybackMode::OnlyForced);
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("eng")), Some(3));
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("jpn")), Some(3));
+
+        let unforced: Vec<_> = tracks.into_iter().filter(|track| !track.is_forced).collect();
+        assert_eq!(preferences.subtitle_stream(&unforced, Some("jpn")), None);
+    }
+
+    #[test]
+    fn none_mode_takes_nothing() {
+        let tracks = subtitle_tracks(&subtitle_source());
+        let preferences = subtitle_preferences(SubtitlePlaybackMode::None);
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("eng")), None);
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("jpn")), None);
+    }
+
+    #[test]
+    fn smart_mode_follows_the_audio() {
+        let tracks = subtitle_tracks(&subtitle_source());
+        let preferences = subtitle_preferences(SubtitlePlaybackMode::Smart);
+        // Audio in the user's language only needs the forced subtitles.
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("eng")), Some(3));
+        assert_eq!(preferences.subtitle_stream(&tracks, Some("jpn")), Some(2));
+        assert_eq!(preferences.subtitle_stream(&tracks, None), Some(2));
+
+        let unforced: Vec<_> = tracks.into_iter().filter(|track| !track.is_forced).collect();
+        assert_eq!(preferences.subtitle_stream(&unforced, Some("eng")), None);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::state::Screen;
+use gpui::{actions, AnyView};
+
+actions!(
+    crabfin,
+    [
+        /// Returns to the previous screen.
+        GoBack,
+        /// Undoes the last `GoBack`.
+        GoForward
+    ]
+);
+
+/// How many screens back the history reaches before the oldest are dropped.
+const MAX_HISTORY: usize = 50;
+
+/// A visited screen and the view that showed it.
+struct Entry {
+    screen: Screen,
+    view: AnyView,
+}
+
+/// Back/forward history of screens.
+///
+/// Views stay alive while they are in the history, so going back shows the
+/// view exactly as it was left: loaded data, inputs and scroll position.
+#[derive(Default)]
+pub struct Router {
+    back: Vec<Entry>,
+    current: Option<Entry>,
+    forward: Vec<Entry>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn current_screen(&self) -> Option<&Screen> {
+        self.current.as_ref().map(|entry| &entry.screen)
+    }
+
+    pub fn current_view(&self) -> Option<AnyView> {
+        self.current.as_ref().map(|entry| entry.view.clone())
+    }
+
+    /// Shows `view` as a new screen. Anything ahead in the forward history is
+    /// dropped, and showing the screen that's already current replaces it.
+    pub fn push(&mut self, screen: Screen, view: AnyView) {
+        if self.current_screen() == Some(&screen) {
+            self.replace(screen, view);
+            return;
+        }
+
+        self.forward.clear();
+        if let Some(current) = self.current.take() {
+            self.back.push(current);
+            if self.back.len() > MAX_HISTORY {
+                self.back.remove(0);
+            }
+        }
+        self.current = Some(Entry { screen, view });
+    }
+
+    /// Swaps the current screen without adding a history entry.
+    pub fn replace(&mut self, screen: Screen, view: AnyView) {
+        self.current = Some(Entry { screen, view });
+    }
+
+    /// Forgets all history, e.g. when the signed-in user changes.
+    pub fn clear(&mut self) {
+        self.back.clear();
+        self.current = None;
+        self.forward.clear();
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    /// Returns `false` if there was nothing to go back to. Leaving the player
+    /// ends playback, so it isn't kept around to go forward to.
+    pub fn back(&mut self) -> bool {
+        let Some(previous) = self.back.pop() else {
+            return false;
+        };
+        if let Some(current) = self.current.replace(previous)
+            && !matches!(current.screen, Screen::Player(_))
+        {
+            self.forward.push(current);
+        }
+        true
+    }
+
+    /// Returns `false` if there was nothing to go forward to.
+    pub fn forward(&mut self) -> bool {
+        let Some(next) = self.forward.pop() else {
+            return false;
+        };
+        if let Some(current) = self.current.replace(next) {
+            self.back.push(current);
+        }
+        true
+    }
+}
@@ -0,0 +1,283 @@
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const SERVICE: &str = "crabfin";
+const NONCE_LEN: usize = 24;
+
+/// Where access tokens live, so that `config.json` only holds a reference to them.
+pub trait SecretStore {
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    fn set(&self, key: &str, secret: &str) -> Result<()>;
+    fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// Picks the platform keyring when one is reachable, otherwise an encrypted
+/// file in `fallback_dir` whose key is kept in `key_dir`. Keyring entries of
+/// an `instance` other than the default one go under a service of its own.
+pub fn default_store(fallback_dir: &Path, key_dir: &Path, instance: Option<&str>) -> Box<dyn SecretStore> {
+    let service = match instance {
+        Some(instance) => format!("{}-{}", SERVICE, instance),
+        None => SERVICE.to_string(),
+    };
+    match KeyringStore::detect(service) {
+        Some(store) => Box::new(store),
+        None => {
+            tracing::info!("No keyring available, storing secrets in {}", fallback_dir.display());
+            Box::new(EncryptedFileStore::new(fallback_dir, key_dir))
+        }
+    }
+}
+
+/// The OS credential store: Secret Service on Linux, Keychain on macOS and
+/// Credential Manager on Windows.
+pub struct KeyringStore {
+    service: String,
+}
+
+impl KeyringStore {
+    /// Returns the store for entries under `service` if a keyring backend
+    /// answers. A lookup of a missing entry succeeds with `NoEntry`; anything
+    /// else means there's no daemon or it refused access.
+    pub fn detect(service: String) -> Option<Self> {
+        let entry = keyring::Entry::new(&service, "probe").ok()?;
+        match entry.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Some(Self { service }),
+            Err(e) => {
+                tracing::debug!("Keyring unavailable: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        match keyring::Entry::new(&self.service, key)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).context("Failed to read from keyring"),
+        }
+    }
+
+    fn set(&self, key: &str, secret: &str) -> Result<()> {
+        keyring::Entry::new(&self.service, key)?
+            .set_password(secret)
+            .context("Failed to write to keyring")
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match keyring::Entry::new(&self.service, key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e).context("Failed to delete from keyring"),
+        }
+    }
+}
+
+/// Fallback for machines without a keyring daemon, such as headless boxes or
+/// minimal window managers.
+///
+/// All secrets are kept in one XChaCha20-Poly1305 encrypted JSON map. The key
+/// sits in a file only the current user can read, in a different directory
+/// than the secrets, so this guards against the secrets file leaking on its
+/// own (backups, dotfile sync of the config dir), not against someone who
+/// already has the user's account.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    key_path: PathBuf,
+    /// Where older versions kept the key, next to the secrets.
+    legacy_key_path: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(dir: &Path, key_dir: &Path) -> Self {
+        Self {
+            path: dir.join("secrets.bin"),
+            key_path: key_dir.join("secrets.key"),
+            legacy_key_path: dir.join("secrets.key"),
+        }
+    }
+
+    /// Loads the key, creating one only while there are no secrets yet. Were
+    /// the key lost with secrets still around, a new one would leave them
+    /// unreadable for good, so that's an error instead.
+    fn cipher(&self) -> Result<XChaCha20Poly1305> {
+        self.move_legacy_key()?;
+        match fs::read(&self.key_path) {
+            Ok(key) => {
+                return XChaCha20Poly1305::new_from_slice(&key).map_err(|_| anyhow!("Secrets key file is corrupt"));
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context("Failed to read secrets key"),
+        }
+
+        if self.path.exists() {
+            return Err(anyhow!(
+                "The key for {} is missing from {}, so the stored sign-ins can't be read. \
+                 Restore the key, or delete the secrets file and sign in again",
+                self.path.display(),
+                self.key_path.display()
+            ));
+        }
+        let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        write_private(&self.key_path, &key).context("Failed to write secrets key")?;
+        Ok(XChaCha20Poly1305::new(&key))
+    }
+
+    /// Moves a key left next to the secrets by an older version to `key_path`.
+    fn move_legacy_key(&self) -> Result<()> {
+        if self.legacy_key_path == self.key_path || !self.legacy_key_path.exists() || self.key_path.exists() {
+            return Ok(());
+        }
+        let key = fs::read(&self.legacy_key_path).context("Failed to read secrets key")?;
+        write_private(&self.key_path, &key).context("Failed to write secrets key")?;
+        fs::remove_file(&self.legacy_key_path).context("Failed to remove the old secrets key")
+    }
+
+    fn read_all(&self) -> Result<BTreeMap<String, String>> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e).context("Failed to read secrets file"),
+        };
+        if data.len() < NONCE_LEN {
+            return Err(anyhow!("Secrets file is truncated"));
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()?
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt secrets file"))?;
+        serde_json::from_slice(&plaintext).context("Failed to parse secrets file")
+    }
+
+    fn write_all(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let cipher = self.cipher()?;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(secrets)?;
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| anyhow!("Failed to encrypt secrets"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        write_private(&self.path, &data).context("Failed to write secrets file")
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.read_all()?.remove(key))
+    }
+
+    fn set(&self, key: &str, secret: &str) -> Result<()> {
+        let mut secrets = self.read_all()?;
+        secrets.insert(key.to_string(), secret.to_string());
+        self.write_all(&secrets)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let mut secrets = self.read_all()?;
+        if secrets.remove(key).is_some() {
+            self.write_all(&secrets)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a file that only the current user can read. The contents go to a
+/// sibling temporary file first and are renamed over `path`, so a crash or a
+/// full disk mid-write leaves the old file intact.
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp_name = path.file_name().context("Secrets path has no file name")?.to_owned();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for one test, holding `secrets/` and `keys/`.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("crabfin-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = test_dir("secrets-round-trip");
+        let store = EncryptedFileStore::new(&dir.join("secrets"), &dir.join("keys"));
+        assert_eq!(store.get("server:user").unwrap(), None);
+
+        store.set("server:user", "token-1").unwrap();
+        store.set("server:other", "token-2").unwrap();
+        store.delete("server:other").unwrap();
+
+        // A second instance, as after a restart, reads the same secrets.
+        let store = EncryptedFileStore::new(&dir.join("secrets"), &dir.join("keys"));
+        assert_eq!(store.get("server:user").unwrap().as_deref(), Some("token-1"));
+        assert_eq!(store.get("server:other").unwrap(), None);
+
+        let data = fs::read(dir.join("secrets/secrets.bin")).unwrap();
+        assert!(!data.windows(7).any(|w| w == b"token-1"));
+        assert!(!dir.join("secrets/secrets.key").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_key_is_an_error() {
+        let dir = test_dir("secrets-missing-key");
+        let store = EncryptedFileStore::new(&dir.join("secrets"), &dir.join("keys"));
+        store.set("server:user", "token").unwrap();
+        let data = fs::read(dir.join("secrets/secrets.bin")).unwrap();
+
+        fs::remove_file(dir.join("keys/secrets.key")).unwrap();
+        assert!(store.get("server:user").is_err());
+        assert!(store.set("server:user", "new token").is_err());
+
+        // Nothing was replaced, so restoring the key recovers the secrets.
+        assert!(!dir.join("keys/secrets.key").exists());
+        assert_eq!(fs::read(dir.join("secrets/secrets.bin")).unwrap(), data);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn moves_key_away_from_secrets() {
+        let dir = test_dir("secrets-legacy-key");
+        EncryptedFileStore::new(&dir.join("secrets"), &dir.join("secrets"))
+            .set("server:user", "token")
+            .unwrap();
+
+        let store = EncryptedFileStore::new(&dir.join("secrets"), &dir.join("keys"));
+        assert_eq!(store.get("server:user").unwrap().as_deref(), Some("token"));
+        assert!(!dir.join("secrets/secrets.key").exists());
+        assert!(dir.join("keys/secrets.key").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,29 +1,57 @@
+use crate::api::{self, DeviceInfo, JellyfinClient};
 use crate::config::Config;
+use crate::secrets::{self, SecretStore};
 
+/// Every place the app can navigate to. The router keeps a history of these.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Screen {
     ServerList,
     AddServer,
+    /// Login form for the server at this URL.
     Login(String),
+    /// Signed-in users of the server with this id.
+    UserPicker(String),
+    Home,
+    /// A library (user view) by id.
+    Library(String),
+    /// An item's detail page by id.
+    Item(String),
+    Search,
+    /// Playback of the item with this id.
+    Player(String),
+    Settings,
 }
 
 pub struct AppState {
     pub config: Config,
-    pub screen: Screen,
+    pub device: DeviceInfo,
+    /// Shared by every API client, so they all draw on one connection pool.
+    pub http: reqwest::Client,
+    pub secrets: Box<dyn SecretStore>,
 }
 
 impl AppState {
     pub fn new() -> Self {
-        let config = Config::load().unwrap_or_default();
-        let screen = if config.servers.is_empty() {
-            Screen::AddServer
-        } else {
-            Screen::ServerList
-        };
+        let config_dir = Config::config_dir().unwrap_or_default();
+        let key_dir = Config::key_dir().unwrap_or_else(|_| config_dir.clone());
+        let secrets = secrets::default_store(&config_dir, &key_dir, Config::instance_id().as_deref());
+        let mut config = Config::load_or_backup(&*secrets);
+        if config.ensure_device_id() {
+            let _ = config.save();
+        }
+        let device = DeviceInfo::new(config.device_id.clone().unwrap_or_default());
+        let http = JellyfinClient::http_client(&device).expect("Failed to set up the HTTP client");
 
         Self {
             config,
-            screen,
+            device,
+            http,
+            secrets,
         }
     }
+
+    /// Creates an API client for `url` that identifies as this device.
+    pub fn client_for(&self, url: &str) -> api::Result<JellyfinClient> {
+        JellyfinClient::new(self.http.clone(), url, self.device.clone())
+    }
 }
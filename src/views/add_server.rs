@@ -1,6 +1,10 @@
+use crate::api::{self, ApiError, DiscoveredServer};
 use gpui::prelude::*;
 use gpui::*;
 use gpui_component::{button::{Button, ButtonVariants}, input::{Input, InputState}, *};
+use std::time::Duration;
+
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
 
 pub struct AddServerView {
     input: Entity<InputState>,
@@ -8,6 +12,8 @@ pub struct AddServerView {
     on_cancel: Box<dyn Fn(&mut Window, &mut Context<AddServerView>) + 'static>,
     is_validating: bool,
     error_message: Option<String>,
+    discovered: Vec<DiscoveredServer>,
+    is_discovering: bool,
 }
 
 impl AddServerView {
@@ -17,15 +23,48 @@ impl AddServerView {
         on_connect: impl Fn(String, &mut Window, &mut Context<AddServerView>) + 'static,
         on_cancel: impl Fn(&mut Window, &mut Context<AddServerView>) + 'static,
     ) -> Self {
-        let input = cx.new(|cx| InputState::new(window, cx).placeholder("Server URL"));
+        let input = cx.new(|cx| InputState::new(window, cx).placeholder("Server address, e.g. 192.168.1.5 or jellyfin.example.com"));
 
-        Self {
+        let mut this = Self {
             input,
             on_connect: Box::new(on_connect),
             on_cancel: Box::new(on_cancel),
             is_validating: false,
             error_message: None,
+            discovered: Vec::new(),
+            is_discovering: false,
+        };
+        this.discover(cx);
+        this
+    }
+
+    /// Broadcasts for Jellyfin servers on the local network and lists the ones that answer.
+    pub fn discover(&mut self, cx: &mut Context<Self>) {
+        if self.is_discovering {
+            return;
         }
+        self.is_discovering = true;
+        cx.notify();
+
+        cx.spawn(async move |this, cx| {
+            let servers = api::discover_servers(DISCOVERY_TIMEOUT).await.unwrap_or_else(|e| {
+                tracing::warn!("Server discovery failed: {}", e);
+                Vec::new()
+            });
+
+            let _ = this.update(cx, |view, cx| {
+                view.discovered = servers;
+                view.is_discovering = false;
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Fills in `address` and connects right away, as if the user had typed it.
+    pub fn connect_to(&mut self, address: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.input.update(cx, |state, cx| state.set_value(address.clone(), window, cx));
+        (self.on_connect)(address, window, cx);
     }
 
     pub fn set_validating(&mut self, validating: bool, cx: &mut Context<Self>) {
@@ -37,6 +76,34 @@ impl AddServerView {
         self.error_message = error;
         cx.notify();
     }
+
+    pub fn set_api_error(&mut self, error: &ApiError, cx: &mut Context<Self>) {
+        self.set_error(Some(Self::describe_error(error)), cx);
+    }
+
+    fn describe_error(error: &ApiError) -> String {
+        match error {
+            ApiError::InvalidUrl(_) => {
+                "That doesn't look like a valid address. Try something like https://jellyfin.example.com".to_string()
+            }
+            ApiError::Connect(_) => {
+                "Could not reach the server. Check the address and that the server is running.".to_string()
+            }
+            ApiError::Tls(_) => {
+                "Secure connection failed. The server's certificate may be self-signed or expired.".to_string()
+            }
+            ApiError::Timeout(_) => {
+                "The server took too long to respond. Check your network and try again.".to_string()
+            }
+            ApiError::NotFound | ApiError::Decode(_) => {
+                "No Jellyfin server found at this address. If it runs under a sub-path, include it (e.g. /jellyfin).".to_string()
+            }
+            ApiError::Server(status) => {
+                format!("The server reported an error ({}). Try again later.", status)
+            }
+            other => format!("Failed to connect: {}", other),
+        }
+    }
 }
 
 impl Render for AddServerView {
@@ -58,6 +125,58 @@ impl Render for AddServerView {
                     .gap_4()
                     .child(div().text_xl().font_bold().child("Add Server"))
                     .child(Input::new(&self.input))
+                    .child(
+                        v_flex()
+                            .gap_2()
+                            .child(
+                                h_flex()
+                                    .justify_between()
+                                    .items_center()
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(theme.muted_foreground)
+                                            .child(if self.is_discovering {
+                                                "Searching your network..."
+                                            } else if self.discovered.is_empty() {
+                                                "No servers found on your network"
+                                            } else {
+                                                "Servers on your network"
+                                            })
+                                    )
+                                    .child(
+                                        Button::new("discover")
+                                            .icon(IconName::Search)
+                                            .ghost()
+                                            .disabled(self.is_discovering)
+                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                this.discover(cx);
+                                            }))
+                                    )
+                            )
+                            .children(self.discovered.iter().enumerate().map(|(i, server)| {
+                                let address = server.address.clone();
+                                div()
+                                    .id(("discovered", i))
+                                    .p_3()
+                                    .border_1()
+                                    .border_color(theme.border)
+                                    .rounded_md()
+                                    .cursor_pointer()
+                                    .hover(|s| s.bg(theme.list_hover))
+                                    .flex()
+                                    .flex_col()
+                                    .child(div().font_bold().child(server.name.clone()))
+                                    .child(div().text_sm().text_color(theme.muted_foreground).child(server.address.clone()))
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.input.update(cx, |state, cx| {
+                                            state.set_value(address.clone(), window, cx);
+                                        });
+                                        this.error_message = None;
+                                        cx.notify();
+                                    }))
+                            }))
+                    )
                     .children(self.error_message.as_ref().map(|msg| {
                         div().text_sm().text_color(theme.danger).child(msg.clone())
                     }))
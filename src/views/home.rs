@@ -0,0 +1,353 @@
+use crate::api::{
+    ApiError, BaseItemDto, BaseItemKind, CollectionType, GetLatestMediaQuery, GetNextUpQuery, GetResumeItemsQuery,
+    GetUserViewsQuery, ItemFields, JellyfinClient, MediaType, UserDto,
+};
+use crate::components::PosterCard;
+use crate::config::Server;
+use crate::images::{Artwork, ImageService};
+use crate::state::Screen;
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::{button::{Button, ButtonVariants}, *};
+
+/// Items per row. The server sorts, so this is the most relevant slice.
+const ROW_LIMIT: i32 = 16;
+
+/// Collections without a meaningful "latest" list.
+const NO_LATEST: &[CollectionType] = &[CollectionType::Boxsets, CollectionType::Playlists, CollectionType::Livetv];
+
+struct HomeRow {
+    title: String,
+    items: Vec<BaseItemDto>,
+    landscape: bool,
+    scroll_handle: ScrollHandle,
+}
+
+impl HomeRow {
+    fn new(title: String, items: Vec<BaseItemDto>, landscape: bool) -> Self {
+        Self {
+            title,
+            items,
+            landscape,
+            scroll_handle: ScrollHandle::new(),
+        }
+    }
+}
+
+pub struct HomeView {
+    client: JellyfinClient,
+    server: Server,
+    user: UserDto,
+    rows: Vec<HomeRow>,
+    scroll_handle: ScrollHandle,
+    images: ImageService,
+    is_loading: bool,
+    error_message: Option<String>,
+    on_navigate: Box<dyn Fn(Screen, &mut Window, &mut Context<HomeView>) + 'static>,
+    on_logout: Box<dyn Fn(&mut Window, &mut Context<HomeView>) + 'static>,
+}
+
+impl HomeView {
+    pub fn new(
+        cx: &mut Context<Self>,
+        client: JellyfinClient,
+        images: ImageService,
+        server: Server,
+        user: UserDto,
+        on_navigate: impl Fn(Screen, &mut Window, &mut Context<HomeView>) + 'static,
+        on_logout: impl Fn(&mut Window, &mut Context<HomeView>) + 'static,
+    ) -> Self {
+        let mut this = Self {
+            client,
+            server,
+            user,
+            rows: Vec::new(),
+            scroll_handle: ScrollHandle::new(),
+            images,
+            is_loading: false,
+            error_message: None,
+            on_navigate: Box::new(on_navigate),
+            on_logout: Box::new(on_logout),
+        };
+        this.load(cx);
+        this
+    }
+
+    /// Fetches every row. Rows that fail are left out; the error is only
+    /// shown when the library list itself can't be loaded.
+    pub fn load(&mut self, cx: &mut Context<Self>) {
+        if self.is_loading {
+            return;
+        }
+        self.is_loading = true;
+        self.error_message = None;
+        cx.notify();
+
+        let client = self.client.clone();
+        let user_id = self.user.id.clone();
+        cx.spawn(async move |this, cx| {
+            let result = fetch_rows(&client, user_id).await;
+            let _ = this.update(cx, |view, cx| {
+                view.is_loading = false;
+                match result {
+                    Ok(rows) => view.rows = rows,
+                    Err(e) => view.error_message = Some(describe_error(&e)),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn render_row(&self, index: usize, row: &HomeRow, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .gap_2()
+            .child(div().px_4().text_lg().font_bold().child(row.title.clone()))
+            .child(
+                h_flex()
+                    .id(("home_row", index))
+                    .px_4()
+                    .pb_2()
+                    .gap_3()
+                    .items_start()
+                    .overflow_x_scroll()
+                    .track_scroll(&row.scroll_handle)
+                    .children(row.items.iter().enumerate().map(|(i, item)| {
+                        let (artwork, size) = card_artwork(item, row.landscape);
+                        let image = self.images.image(artwork.as_ref(), size.width, cx);
+                        let (title, subtitle) = card_titles(item);
+                        let progress = item
+                            .user_data
+                            .as_ref()
+                            .and_then(|data| data.played_percentage)
+                            .map(|p| (p / 100.) as f32);
+
+                        let screen = screen_for(item);
+                        PosterCard::new(("home_card", index * 1000 + i), title, image)
+                            .subtitle(subtitle)
+                            .progress(progress)
+                            .with_size(size)
+                            .when_some(screen, |card, screen| {
+                                card.on_click(cx.listener(move |this, _, window, cx| {
+                                    (this.on_navigate)(screen.clone(), window, cx);
+                                }))
+                            })
+                    }))
+            )
+    }
+}
+
+/// Loads libraries, resume, next up and the latest items of each library.
+async fn fetch_rows(client: &JellyfinClient, user_id: Option<String>) -> Result<Vec<HomeRow>, ApiError> {
+    let fields = Some(vec![ItemFields::PrimaryImageAspectRatio]);
+
+    let (views, resume, next_up) = tokio::join!(
+        client.get_user_views(&GetUserViewsQuery {
+            user_id: user_id.clone(),
+            ..Default::default()
+        }),
+        client.get_resume_items(&GetResumeItemsQuery {
+            user_id: user_id.clone(),
+            limit: Some(ROW_LIMIT),
+            media_types: Some(vec![MediaType::Video]),
+            fields: fields.clone(),
+            enable_user_data: Some(true),
+            ..Default::default()
+        }),
+        client.get_next_up(&GetNextUpQuery {
+            user_id: user_id.clone(),
+            limit: Some(ROW_LIMIT),
+            fields: fields.clone(),
+            enable_user_data: Some(true),
+            ..Default::default()
+        }),
+    );
+
+    // Without the library list there's no home screen worth showing.
+    let libraries = views?.items.unwrap_or_default();
+    let mut rows = vec![HomeRow::new("My Media".to_string(), libraries.clone(), true)];
+
+    match resume {
+        Ok(result) => rows.push(HomeRow::new("Continue Watching".to_string(), result.items.unwrap_or_default(), true)),
+        Err(e) => tracing::warn!("Failed to load resume items: {}", e),
+    }
+    match next_up {
+        Ok(result) => rows.push(HomeRow::new("Next Up".to_string(), result.items.unwrap_or_default(), true)),
+        Err(e) => tracing::warn!("Failed to load next up: {}", e),
+    }
+    // Empty resume and next up rows are just noise.
+    rows.retain(|row| !row.items.is_empty());
+
+    for library in &libraries {
+        if library.collection_type.is_some_and(|t| NO_LATEST.contains(&t)) {
+            continue;
+        }
+        let query = GetLatestMediaQuery {
+            user_id: user_id.clone(),
+            parent_id: library.id.clone(),
+            limit: Some(ROW_LIMIT),
+            fields: fields.clone(),
+            enable_user_data: Some(true),
+            group_items: Some(true),
+            ..Default::default()
+        };
+        match client.get_latest_media(&query).await {
+            Ok(items) if !items.is_empty() => {
+                let title = format!("Latest {}", library.name.as_deref().unwrap_or_default());
+                rows.push(HomeRow::new(title, items, false));
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to load latest items: {}", e),
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Libraries open in the library browser, everything else on its detail page.
+fn screen_for(item: &BaseItemDto) -> Option<Screen> {
+    let id = item.id.clone()?;
+    match item.type_ {
+        Some(BaseItemKind::CollectionFolder | BaseItemKind::UserView) => Some(Screen::Library(id)),
+        _ => Some(Screen::Item(id)),
+    }
+}
+
+fn card_artwork(item: &BaseItemDto, landscape: bool) -> (Option<Artwork>, Size<Pixels>) {
+    if landscape {
+        (Artwork::landscape(item), PosterCard::LANDSCAPE)
+    } else {
+        (Artwork::portrait(item), PosterCard::PORTRAIT)
+    }
+}
+
+/// Episodes are titled by their series with "S1:E2 - Name" below it.
+fn card_titles(item: &BaseItemDto) -> (String, Option<String>) {
+    let name = item.name.clone().unwrap_or_default();
+    if item.type_ != Some(BaseItemKind::Episode) {
+        return (name, item.production_year.map(|year| year.to_string()));
+    }
+
+    let series = item.series_name.clone().unwrap_or_default();
+    let subtitle = match (item.parent_index_number, item.index_number) {
+        (Some(season), Some(episode)) => format!("S{}:E{} - {}", season, episode, name),
+        _ => name,
+    };
+    (series, Some(subtitle))
+}
+
+fn describe_error(error: &ApiError) -> String {
+    match error {
+        ApiError::Connect(_) | ApiError::Timeout(_) => {
+            "Could not reach the server. Check your connection and try again.".to_string()
+        }
+        ApiError::Unauthorized => "Your session has expired. Please sign in again.".to_string(),
+        other => format!("Failed to load your media: {}", other),
+    }
+}
+
+impl Render for HomeView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let user_name = self.user.name.clone().unwrap_or_default();
+        let rows: Vec<_> = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| self.render_row(i, row, cx).into_any_element())
+            .collect();
+        let theme = cx.theme();
+
+        div()
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(theme.background)
+            .child(
+                h_flex()
+                    .p_4()
+                    .justify_between()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(theme.border)
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .child(div().text_xl().font_bold().child(self.server.name.clone()))
+                            .child(div().text_sm().text_color(theme.muted_foreground).child(user_name))
+                    )
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                Button::new("search")
+                                    .ghost()
+                                    .icon(IconName::Search)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        (this.on_navigate)(Screen::Search, window, cx);
+                                    }))
+                            )
+                            .child(
+                                Button::new("settings")
+                                    .ghost()
+                                    .label("Settings")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        (this.on_navigate)(Screen::Settings, window, cx);
+                                    }))
+                            )
+                            .child(
+                                Button::new("switch_server")
+                                    .ghost()
+                                    .label("Switch Server")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        (this.on_navigate)(Screen::ServerList, window, cx);
+                                    }))
+                            )
+                            .child(
+                                Button::new("switch_user")
+                                    .ghost()
+                                    .label("Switch User")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        let screen = Screen::UserPicker(this.server.id.clone());
+                                        (this.on_navigate)(screen, window, cx);
+                                    }))
+                            )
+                            .child(
+                                Button::new("logout")
+                                    .ghost()
+                                    .label("Sign Out")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        (this.on_logout)(window, cx);
+                                    }))
+                            )
+                    )
+            )
+            .child(
+                v_flex()
+                    .id("home_rows")
+                    .flex_1()
+                    .py_4()
+                    .gap_6()
+                    .overflow_y_scroll()
+                    .track_scroll(&self.scroll_handle)
+                    .when(self.is_loading && self.rows.is_empty(), |this| {
+                        this.child(div().px_4().text_sm().text_color(theme.muted_foreground).child("Loading..."))
+                    })
+                    .when_some(self.error_message.clone(), |this, message| {
+                        this.child(
+                            h_flex()
+                                .px_4()
+                                .gap_2()
+                                .items_center()
+                                .child(div().text_sm().text_color(theme.danger).child(message))
+                                .child(
+                                    Button::new("retry")
+                                        .label("Retry")
+                                        .on_click(cx.listener(|this, _, _window, cx| this.load(cx)))
+                                )
+                        )
+                    })
+                    .children(rows)
+            )
+    }
+}
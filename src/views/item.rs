@@ -0,0 +1,693 @@
+use crate::api::{
+    ApiError, BaseItemDto, BaseItemKind, GetEpisodesQuery, GetItemQuery, GetSeasonsQuery, ItemFields, JellyfinClient,
+    MarkFavoriteItemQuery, MarkPlayedItemQuery, MarkUnplayedItemQuery, MediaStream, MediaStreamType, PersonKind,
+    UnmarkFavoriteItemQuery, UserItemDataDto, VideoRangeType,
+};
+use crate::components::PosterCard;
+use crate::images::{Artwork, ImageService};
+use crate::state::Screen;
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::{button::{Button, ButtonVariants}, *};
+
+/// Jellyfin measures time in 100ns ticks.
+const TICKS_PER_SECOND: i64 = 10_000_000;
+
+const BACKDROP_HEIGHT: Pixels = px(360.);
+/// Backdrops span the window; this is a full HD one once doubled for HiDPI.
+const BACKDROP_WIDTH: Pixels = px(960.);
+const LOGO_SIZE: Size<Pixels> = Size { width: px(360.), height: px(120.) };
+const CAST_SIZE: Size<Pixels> = Size { width: px(120.), height: px(180.) };
+
+/// Cast beyond this is rarely scrolled to and costs an image request each.
+const CAST_LIMIT: usize = 30;
+
+/// The detail page of a movie, series, season or episode.
+pub struct ItemView {
+    client: JellyfinClient,
+    user_id: Option<String>,
+    item_id: String,
+    item: Option<BaseItemDto>,
+    seasons: Vec<BaseItemDto>,
+    selected_season_id: Option<String>,
+    episodes: Vec<BaseItemDto>,
+    images: ImageService,
+    is_loading: bool,
+    error_message: Option<String>,
+    scroll_handle: ScrollHandle,
+    cast_scroll_handle: ScrollHandle,
+    on_navigate: Box<dyn Fn(Screen, &mut Window, &mut Context<ItemView>) + 'static>,
+    on_back: Box<dyn Fn(&mut Window, &mut Context<ItemView>) + 'static>,
+}
+
+impl ItemView {
+    pub fn new(
+        cx: &mut Context<Self>,
+        client: JellyfinClient,
+        images: ImageService,
+        user_id: Option<String>,
+        item_id: String,
+        on_navigate: impl Fn(Screen, &mut Window, &mut Context<ItemView>) + 'static,
+        on_back: impl Fn(&mut Window, &mut Context<ItemView>) + 'static,
+    ) -> Self {
+        let mut this = Self {
+            client,
+            user_id,
+            item_id,
+            item: None,
+            seasons: Vec::new(),
+            selected_season_id: None,
+            episodes: Vec::new(),
+            images,
+            is_loading: false,
+            error_message: None,
+            scroll_handle: ScrollHandle::new(),
+            cast_scroll_handle: ScrollHandle::new(),
+            on_navigate: Box::new(on_navigate),
+            on_back: Box::new(on_back),
+        };
+        this.load(cx);
+        this
+    }
+
+    /// Fetches the item, then its seasons or episodes when it has any.
+    pub fn load(&mut self, cx: &mut Context<Self>) {
+        if self.is_loading {
+            return;
+        }
+        self.is_loading = true;
+        self.error_message = None;
+        cx.notify();
+
+        let client = self.client.clone();
+        let item_id = self.item_id.clone();
+        let query = GetItemQuery {
+            user_id: self.user_id.clone(),
+        };
+        cx.spawn(async move |this, cx| {
+            let result = client.get_item(&item_id, &query).await;
+            let _ = this.update(cx, |view, cx| {
+                view.is_loading = false;
+                match result {
+                    Ok(item) => {
+                        match item.type_ {
+                            Some(BaseItemKind::Series) => view.load_seasons(item_id.clone(), cx),
+                            Some(BaseItemKind::Season) => {
+                                if let Some(series_id) = item.series_id.clone() {
+                                    view.load_episodes(series_id, item_id.clone(), cx);
+                                }
+                            }
+                            _ => {}
+                        }
+                        view.item = Some(item);
+                    }
+                    Err(e) => view.error_message = Some(describe_error(&e)),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Loads a series' seasons and opens the first one that isn't finished.
+    fn load_seasons(&mut self, series_id: String, cx: &mut Context<Self>) {
+        let client = self.client.clone();
+        let query = GetSeasonsQuery {
+            user_id: self.user_id.clone(),
+            fields: Some(vec![ItemFields::PrimaryImageAspectRatio]),
+            enable_user_data: Some(true),
+            ..Default::default()
+        };
+        cx.spawn(async move |this, cx| {
+            let result = client.get_seasons(&series_id, &query).await;
+            let _ = this.update(cx, |view, cx| {
+                match result {
+                    Ok(result) => view.seasons = result.items.unwrap_or_default(),
+                    Err(e) => tracing::warn!("Failed to load seasons of {}: {}", series_id, e),
+                }
+                let season = view
+                    .seasons
+                    .iter()
+                    .find(|season| !season.user_data.as_ref().and_then(|data| data.played).unwrap_or(false))
+                    .or(view.seasons.first())
+                    .and_then(|season| season.id.clone());
+                if let Some(season_id) = season {
+                    view.load_episodes(series_id.clone(), season_id, cx);
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn load_episodes(&mut self, series_id: String, season_id: String, cx: &mut Context<Self>) {
+        self.selected_season_id = Some(season_id.clone());
+        self.episodes.clear();
+        cx.notify();
+
+        let client = self.client.clone();
+        let query = GetEpisodesQuery {
+            user_id: self.user_id.clone(),
+            season_id: Some(season_id.clone()),
+            fields: Some(vec![ItemFields::Overview, ItemFields::PrimaryImageAspectRatio]),
+            enable_user_data: Some(true),
+            ..Default::default()
+        };
+        cx.spawn(async move |this, cx| {
+            let result = client.get_episodes(&series_id, &query).await;
+            let _ = this.update(cx, |view, cx| {
+                // The user may have picked another season meanwhile.
+                if view.selected_season_id.as_deref() != Some(season_id.as_str()) {
+                    return;
+                }
+                match result {
+                    Ok(result) => view.episodes = result.items.unwrap_or_default(),
+                    Err(e) => tracing::warn!("Failed to load episodes of {}: {}", season_id, e),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn select_season(&mut self, season_id: String, cx: &mut Context<Self>) {
+        if self.selected_season_id.as_deref() == Some(season_id.as_str()) {
+            return;
+        }
+        self.load_episodes(self.item_id.clone(), season_id, cx);
+    }
+
+    /// What the play button starts: the item itself, or for a series or
+    /// season the first episode not yet watched.
+    fn play_target(&self) -> Option<&BaseItemDto> {
+        let item = self.item.as_ref()?;
+        match item.type_ {
+            Some(BaseItemKind::Series | BaseItemKind::Season) => self
+                .episodes
+                .iter()
+                .find(|episode| !episode.user_data.as_ref().and_then(|data| data.played).unwrap_or(false))
+                .or(self.episodes.first()),
+            _ => Some(item),
+        }
+    }
+
+    fn toggle_played(&mut self, cx: &mut Context<Self>) {
+        let Some(item) = &self.item else { return };
+        let played = item.user_data.as_ref().and_then(|data| data.played).unwrap_or(false);
+        let client = self.client.clone();
+        let item_id = self.item_id.clone();
+        let user_id = self.user_id.clone();
+
+        cx.spawn(async move |this, cx| {
+            let result = if played {
+                client.mark_unplayed_item(&item_id, &MarkUnplayedItemQuery { user_id }).await
+            } else {
+                let query = MarkPlayedItemQuery {
+                    user_id,
+                    ..Default::default()
+                };
+                client.mark_played_item(&item_id, &query).await
+            };
+            let _ = this.update(cx, |view, cx| view.apply_user_data(result, cx));
+        })
+        .detach();
+    }
+
+    fn toggle_favorite(&mut self, cx: &mut Context<Self>) {
+        let Some(item) = &self.item else { return };
+        let favorite = item.user_data.as_ref().and_then(|data| data.is_favorite).unwrap_or(false);
+        let client = self.client.clone();
+        let item_id = self.item_id.clone();
+        let user_id = self.user_id.clone();
+
+        cx.spawn(async move |this, cx| {
+            let result = if favorite {
+                client.unmark_favorite_item(&item_id, &UnmarkFavoriteItemQuery { user_id }).await
+            } else {
+                client.mark_favorite_item(&item_id, &MarkFavoriteItemQuery { user_id }).await
+            };
+            let _ = this.update(cx, |view, cx| view.apply_user_data(result, cx));
+        })
+        .detach();
+    }
+
+    /// Stores the user data the server answered with. Marking a series or
+    /// season played changes its episodes too, so those are refetched.
+    fn apply_user_data(&mut self, result: Result<UserItemDataDto, ApiError>, cx: &mut Context<Self>) {
+        let data = match result {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!("Failed to update {}: {}", self.item_id, e);
+                return;
+            }
+        };
+        let Some(item) = &mut self.item else { return };
+        item.user_data = Some(data);
+        let (kind, series_id) = (item.type_, item.series_id.clone());
+
+        match (kind, self.selected_season_id.clone()) {
+            (Some(BaseItemKind::Series), Some(season_id)) => {
+                self.load_episodes(self.item_id.clone(), season_id, cx);
+            }
+            (Some(BaseItemKind::Season), _) => {
+                if let Some(series_id) = series_id {
+                    self.load_episodes(series_id, self.item_id.clone(), cx);
+                }
+            }
+            _ => {}
+        }
+        cx.notify();
+    }
+
+    fn render_actions(&self, item: &BaseItemDto, cx: &Context<Self>) -> impl IntoElement {
+        let user_data = item.user_data.as_ref();
+        let played = user_data.and_then(|data| data.played).unwrap_or(false);
+        let favorite = user_data.and_then(|data| data.is_favorite).unwrap_or(false);
+
+        let target = self.play_target();
+        let resume_ticks = target
+            .and_then(|target| target.user_data.as_ref())
+            .and_then(|data| data.playback_position_ticks)
+            .filter(|ticks| *ticks > 0);
+        let play_label = match resume_ticks {
+            Some(ticks) => format!("Resume from {}", format_position(ticks)),
+            None => "Play".to_string(),
+        };
+        let target_id = target.and_then(|target| target.id.clone());
+
+        h_flex()
+            .gap_2()
+            .child(
+                Button::new("play")
+                    .primary()
+                    .label(play_label)
+                    .disabled(target_id.is_none())
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        if let Some(id) = target_id.clone() {
+                            (this.on_navigate)(Screen::Player(id), window, cx);
+                        }
+                    }))
+            )
+            .child(
+                Button::new("played")
+                    .when(played, |b| b.primary())
+                    .when(!played, |b| b.ghost())
+                    .label(if played { "Played ✓" } else { "Mark Played" })
+                    .on_click(cx.listener(|this, _, _window, cx| this.toggle_played(cx)))
+            )
+            .child(
+                Button::new("favorite")
+                    .when(favorite, |b| b.primary())
+                    .when(!favorite, |b| b.ghost())
+                    .label(if favorite { "♥ Favorite" } else { "♡ Favorite" })
+                    .on_click(cx.listener(|this, _, _window, cx| this.toggle_favorite(cx)))
+            )
+    }
+
+    fn render_cast(&self, item: &BaseItemDto, cx: &Context<Self>) -> Option<impl IntoElement> {
+        let people: Vec<_> = item.people.iter().flatten().take(CAST_LIMIT).collect();
+        if people.is_empty() {
+            return None;
+        }
+
+        Some(
+            v_flex()
+                .gap_2()
+                .child(div().text_lg().font_bold().child("Cast & Crew"))
+                .child(
+                    h_flex()
+                        .id("cast")
+                        .pb_2()
+                        .gap_3()
+                        .items_start()
+                        .overflow_x_scroll()
+                        .track_scroll(&self.cast_scroll_handle)
+                        .children(people.into_iter().enumerate().map(|(i, person)| {
+                            let role = match person.type_ {
+                                Some(PersonKind::Actor | PersonKind::GuestStar) => person.role.clone(),
+                                Some(kind) => Some(person_kind_label(kind).to_string()),
+                                None => None,
+                            }
+                            .filter(|role| !role.is_empty());
+                            let image = self.images.image(Artwork::person(person).as_ref(), CAST_SIZE.width, cx);
+                            PosterCard::new(("person", i), person.name.clone().unwrap_or_default(), image)
+                                .subtitle(role)
+                                .with_size(CAST_SIZE)
+                        }))
+                ),
+        )
+    }
+
+    fn render_episodes(&self, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        v_flex()
+            .gap_3()
+            .when(!self.seasons.is_empty(), |this| {
+                this.child(h_flex().gap_1().flex_wrap().children(self.seasons.iter().enumerate().filter_map(
+                    |(i, season)| {
+                        let id = season.id.clone()?;
+                        let selected = self.selected_season_id.as_deref() == Some(id.as_str());
+                        let button = Button::new(("season", i)).label(season.name.clone().unwrap_or_default());
+                        let button = if selected { button.primary() } else { button.ghost() };
+                        Some(button.on_click(cx.listener(move |this, _, _window, cx| {
+                            this.select_season(id.clone(), cx);
+                        })))
+                    },
+                )))
+            })
+            .children(self.episodes.iter().enumerate().map(|(i, episode)| {
+                let title = match episode.index_number {
+                    Some(number) => format!("{}. {}", number, episode.name.as_deref().unwrap_or_default()),
+                    None => episode.name.clone().unwrap_or_default(),
+                };
+                let progress = episode
+                    .user_data
+                    .as_ref()
+                    .and_then(|data| data.played_percentage)
+                    .map(|p| (p / 100.) as f32);
+                let runtime = episode.run_time_ticks.map(format_runtime);
+                let image =
+                    self.images.image(Artwork::landscape(episode).as_ref(), PosterCard::LANDSCAPE.width, cx);
+                let id = episode.id.clone();
+
+                h_flex()
+                    .gap_4()
+                    .items_start()
+                    .child(
+                        PosterCard::new(("episode", i), title, image)
+                            .subtitle(runtime)
+                            .progress(progress)
+                            .with_size(PosterCard::LANDSCAPE)
+                            .when_some(id, |card, id| {
+                                card.on_click(cx.listener(move |this, _, window, cx| {
+                                    (this.on_navigate)(Screen::Item(id.clone()), window, cx);
+                                }))
+                            })
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .line_clamp(5)
+                            .child(episode.overview.clone().unwrap_or_default())
+                    )
+            }))
+    }
+
+    fn render_details(&self, item: &BaseItemDto, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let label = |text: &'static str| div().w_24().flex_none().text_color(theme.muted_foreground).child(text);
+        let line = |title: &'static str, value: String| {
+            h_flex().gap_2().items_start().text_sm().child(label(title)).child(div().flex_1().child(value))
+        };
+
+        let genres = item.genres.iter().flatten().cloned().collect::<Vec<_>>().join(", ");
+        let studios = item
+            .studios
+            .iter()
+            .flatten()
+            .filter_map(|studio| studio.name.clone())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let directors = item
+            .people
+            .iter()
+            .flatten()
+            .filter(|person| person.type_ == Some(PersonKind::Director))
+            .filter_map(|person| person.name.clone())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let streams = item
+            .media_sources
+            .as_ref()
+            .and_then(|sources| sources.first())
+            .and_then(|source| source.media_streams.as_ref())
+            .or(item.media_streams.as_ref());
+        let streams_of = |kind: MediaStreamType| -> Vec<&MediaStream> {
+            streams.iter().flat_map(|streams| streams.iter()).filter(|s| s.type_ == Some(kind)).collect()
+        };
+        let video = streams_of(MediaStreamType::Video).first().map(|stream| describe_video(stream));
+        let audio = streams_of(MediaStreamType::Audio).into_iter().map(describe_stream).collect::<Vec<_>>();
+        let subtitles = streams_of(MediaStreamType::Subtitle).into_iter().map(describe_stream).collect::<Vec<_>>();
+
+        v_flex()
+            .gap_1()
+            .when(!genres.is_empty(), |this| this.child(line("Genres", genres)))
+            .when(!directors.is_empty(), |this| this.child(line("Director", directors)))
+            .when(!studios.is_empty(), |this| this.child(line("Studios", studios)))
+            .when_some(video, |this, video| this.child(line("Video", video)))
+            .when(!audio.is_empty(), |this| this.child(line("Audio", audio.join("\n"))))
+            .when(!subtitles.is_empty(), |this| this.child(line("Subtitles", subtitles.join("\n"))))
+    }
+}
+
+/// "2024 · 1h 52m · PG-13 · ★ 7.8", leaving out what the item doesn't have.
+fn summary_line(item: &BaseItemDto) -> String {
+    let mut parts = Vec::new();
+    if let (Some(season), Some(episode)) = (item.parent_index_number, item.index_number)
+        && item.type_ == Some(BaseItemKind::Episode)
+    {
+        parts.push(format!("S{}:E{}", season, episode));
+    }
+    if let Some(year) = item.production_year {
+        parts.push(year.to_string());
+    }
+    if let Some(ticks) = item.run_time_ticks {
+        parts.push(format_runtime(ticks));
+    }
+    if let Some(rating) = item.official_rating.clone() {
+        parts.push(rating);
+    }
+    if let Some(rating) = item.community_rating {
+        parts.push(format!("★ {:.1}", rating));
+    }
+    parts.join(" · ")
+}
+
+/// "1h 52m", or "45m" for anything under an hour.
+fn format_runtime(ticks: i64) -> String {
+    let minutes = ticks / TICKS_PER_SECOND / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// A playback position as "42:10" or "1:02:03".
+fn format_position(ticks: i64) -> String {
+    let seconds = ticks / TICKS_PER_SECOND;
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+/// "HEVC 3840×2160 HDR".
+fn describe_video(stream: &MediaStream) -> String {
+    let mut parts = Vec::new();
+    if let Some(codec) = &stream.codec {
+        parts.push(codec.to_uppercase());
+    }
+    if let (Some(width), Some(height)) = (stream.width, stream.height) {
+        parts.push(format!("{}×{}", width, height));
+    }
+    match stream.video_range_type {
+        None | Some(VideoRangeType::Unknown | VideoRangeType::Unrecognized | VideoRangeType::SDR) => {}
+        Some(range) => parts.push(range.as_str().to_string()),
+    }
+    parts.join(" ")
+}
+
+/// The server's display title, which already has language, codec and channels.
+fn describe_stream(stream: &MediaStream) -> String {
+    stream
+        .display_title
+        .clone()
+        .or_else(|| stream.title.clone())
+        .or_else(|| stream.language.clone())
+        .or_else(|| stream.codec.as_ref().map(|codec| codec.to_uppercase()))
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+fn person_kind_label(kind: PersonKind) -> &'static str {
+    match kind {
+        PersonKind::Director => "Director",
+        PersonKind::Writer => "Writer",
+        PersonKind::Producer => "Producer",
+        PersonKind::Composer => "Composer",
+        PersonKind::Creator => "Creator",
+        _ => "Crew",
+    }
+}
+
+fn describe_error(error: &ApiError) -> String {
+    match error {
+        ApiError::Connect(_) | ApiError::Timeout(_) => {
+            "Could not reach the server. Check your connection and try again.".to_string()
+        }
+        ApiError::NotFound => "This item no longer exists on the server.".to_string(),
+        other => format!("Failed to load this item: {}", other),
+    }
+}
+
+impl Render for ItemView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let item = self.item.clone();
+        let title = item.as_ref().and_then(|item| item.name.clone()).unwrap_or_default();
+        let theme = cx.theme();
+
+        let content = item.map(|item| {
+            let backdrop = self.images.image(Artwork::backdrop(&item).as_ref(), BACKDROP_WIDTH, cx);
+            let logo = self.images.image(Artwork::logo(&item).as_ref(), LOGO_SIZE.width, cx);
+            let poster = self.images.image(Artwork::portrait(&item).as_ref(), PosterCard::PORTRAIT.width, cx);
+            let series = item
+                .series_id
+                .clone()
+                .zip(item.series_name.clone())
+                .filter(|_| item.type_ != Some(BaseItemKind::Series));
+            let tagline = item.taglines.as_ref().and_then(|taglines| taglines.first()).cloned();
+            let has_episodes = matches!(item.type_, Some(BaseItemKind::Series | BaseItemKind::Season));
+
+            v_flex()
+                .gap_6()
+                .pb_6()
+                .child(
+                    div()
+                        .relative()
+                        .w_full()
+                        .h(BACKDROP_HEIGHT)
+                        .bg(theme.muted)
+                        .overflow_hidden()
+                        .when_some(backdrop, |this, image| {
+                            this.child(img(image).size_full().object_fit(ObjectFit::Cover))
+                        })
+                        .child(
+                            div()
+                                .absolute()
+                                .bottom_0()
+                                .left_0()
+                                .w_full()
+                                .h(relative(0.5))
+                                .bg(linear_gradient(
+                                    0.,
+                                    linear_color_stop(theme.background, 0.),
+                                    linear_color_stop(theme.background.opacity(0.), 1.),
+                                ))
+                        )
+                        .child(
+                            div()
+                                .absolute()
+                                .bottom_4()
+                                .left_6()
+                                .map(|this| match logo {
+                                    Some(logo) => this.child(
+                                        img(logo)
+                                            .w(LOGO_SIZE.width)
+                                            .h(LOGO_SIZE.height)
+                                            .object_fit(ObjectFit::Contain)
+                                    ),
+                                    None => this.text_3xl().font_bold().child(title.clone()),
+                                })
+                        )
+                )
+                .child(
+                    h_flex()
+                        .px_6()
+                        .gap_6()
+                        .items_start()
+                        .child(
+                            div()
+                                .w(PosterCard::PORTRAIT.width)
+                                .h(PosterCard::PORTRAIT.height)
+                                .flex_none()
+                                .rounded_md()
+                                .overflow_hidden()
+                                .bg(theme.muted)
+                                .when_some(poster, |this, image| {
+                                    this.child(img(image).size_full().object_fit(ObjectFit::Cover))
+                                })
+                        )
+                        .child(
+                            v_flex()
+                                .flex_1()
+                                .gap_3()
+                                .when_some(series, |this, (series_id, series_name)| {
+                                    this.child(
+                                        div()
+                                            .id("series_link")
+                                            .text_lg()
+                                            .cursor_pointer()
+                                            .hover(|style| style.underline())
+                                            .child(series_name)
+                                            .on_click(cx.listener(move |this, _, window, cx| {
+                                                (this.on_navigate)(Screen::Item(series_id.clone()), window, cx);
+                                            }))
+                                    )
+                                })
+                                .child(div().text_sm().text_color(theme.muted_foreground).child(summary_line(&item)))
+                                .child(self.render_actions(&item, cx))
+                                .when_some(tagline, |this, tagline| {
+                                    this.child(div().italic().child(tagline))
+                                })
+                                .when_some(item.overview.clone(), |this, overview| {
+                                    this.child(div().max_w(px(800.)).child(overview))
+                                })
+                                .child(self.render_details(&item, cx))
+                        )
+                )
+                .when(has_episodes, |this| this.child(div().px_6().child(self.render_episodes(cx))))
+                .children(self.render_cast(&item, cx).map(|cast| div().px_6().child(cast)))
+        });
+
+        v_flex()
+            .size_full()
+            .bg(theme.background)
+            .child(
+                h_flex()
+                    .p_4()
+                    .gap_3()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(theme.border)
+                    .child(
+                        Button::new("back")
+                            .ghost()
+                            .icon(IconName::ArrowLeft)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                (this.on_back)(window, cx);
+                            }))
+                    )
+                    .child(div().text_xl().font_bold().truncate().child(title.clone()))
+            )
+            .child(
+                v_flex()
+                    .id("item_details")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .track_scroll(&self.scroll_handle)
+                    .when(self.is_loading && content.is_none(), |this| {
+                        this.child(div().p_4().text_sm().text_color(theme.muted_foreground).child("Loading..."))
+                    })
+                    .when_some(self.error_message.clone(), |this, message| {
+                        this.child(
+                            h_flex()
+                                .p_4()
+                                .gap_2()
+                                .items_center()
+                                .child(div().text_sm().text_color(theme.danger).child(message))
+                                .child(
+                                    Button::new("retry")
+                                        .label("Retry")
+                                        .on_click(cx.listener(|this, _, _window, cx| this.load(cx)))
+                                )
+                        )
+                    })
+                    .children(content)
+            )
+    }
+}
@@ -0,0 +1,594 @@
+use crate::api::{
+    ApiError, BaseItemDto, BaseItemKind, CollectionType, GetItemQuery, GetItemsQuery, GetQueryFiltersLegacyQuery,
+    GetQueryFiltersQuery, ItemFields, ItemFilter, ItemSortBy, JellyfinClient, NameGuidPair, SortOrder,
+};
+use crate::components::PosterCard;
+use crate::images::{Artwork, ImageService};
+use crate::state::Screen;
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::{button::{Button, ButtonVariants}, *};
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// Items fetched per `/Items` request.
+const PAGE_SIZE: i32 = 100;
+
+const GRID_PADDING: Pixels = px(16.);
+const GRID_GAP: Pixels = px(12.);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LibrarySort {
+    Name,
+    DateAdded,
+    ReleaseDate,
+    Rating,
+    Runtime,
+}
+
+impl LibrarySort {
+    const ALL: [Self; 5] = [Self::Name, Self::DateAdded, Self::ReleaseDate, Self::Rating, Self::Runtime];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::DateAdded => "Date Added",
+            Self::ReleaseDate => "Release Date",
+            Self::Rating => "Rating",
+            Self::Runtime => "Runtime",
+        }
+    }
+
+    /// Ties are broken by name so paging stays stable.
+    fn sort_by(self) -> Vec<ItemSortBy> {
+        match self {
+            Self::Name => vec![ItemSortBy::SortName],
+            Self::DateAdded => vec![ItemSortBy::DateCreated, ItemSortBy::SortName],
+            Self::ReleaseDate => vec![ItemSortBy::PremiereDate, ItemSortBy::ProductionYear, ItemSortBy::SortName],
+            Self::Rating => vec![ItemSortBy::CommunityRating, ItemSortBy::SortName],
+            Self::Runtime => vec![ItemSortBy::Runtime, ItemSortBy::SortName],
+        }
+    }
+
+    /// Newest, best rated and longest first; names A to Z.
+    fn default_descending(self) -> bool {
+        self != Self::Name
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlayedFilter {
+    All,
+    Unplayed,
+    Played,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct LibraryFilters {
+    genre_ids: BTreeSet<String>,
+    years: BTreeSet<i32>,
+    official_ratings: BTreeSet<String>,
+    played: Option<bool>,
+    favorites: bool,
+}
+
+impl LibraryFilters {
+    fn played_filter(&self) -> PlayedFilter {
+        match self.played {
+            None => PlayedFilter::All,
+            Some(false) => PlayedFilter::Unplayed,
+            Some(true) => PlayedFilter::Played,
+        }
+    }
+
+    fn item_filters(&self) -> Option<Vec<ItemFilter>> {
+        let mut filters = Vec::new();
+        match self.played {
+            Some(true) => filters.push(ItemFilter::IsPlayed),
+            Some(false) => filters.push(ItemFilter::IsUnplayed),
+            None => {}
+        }
+        if self.favorites {
+            filters.push(ItemFilter::IsFavorite);
+        }
+        (!filters.is_empty()).then_some(filters)
+    }
+}
+
+/// Values the library actually has, so every filter choice matches something.
+#[derive(Debug, Clone, Default)]
+struct AvailableFilters {
+    genres: Vec<NameGuidPair>,
+    years: Vec<i32>,
+    official_ratings: Vec<String>,
+}
+
+/// A paged, virtualized poster grid of one library with sorting and filters.
+pub struct LibraryView {
+    client: JellyfinClient,
+    user_id: Option<String>,
+    library_id: String,
+    title: String,
+    item_types: Option<Vec<BaseItemKind>>,
+    items: Vec<BaseItemDto>,
+    total: Option<usize>,
+    /// Bumped whenever sorting or filters change, so pages requested for the
+    /// old order are dropped when they arrive.
+    generation: usize,
+    is_loading: bool,
+    error_message: Option<String>,
+    sort: LibrarySort,
+    descending: bool,
+    filters: LibraryFilters,
+    available: AvailableFilters,
+    show_filters: bool,
+    columns: usize,
+    images: ImageService,
+    scroll_handle: UniformListScrollHandle,
+    on_navigate: Box<dyn Fn(Screen, &mut Window, &mut Context<LibraryView>) + 'static>,
+    on_back: Box<dyn Fn(&mut Window, &mut Context<LibraryView>) + 'static>,
+}
+
+impl LibraryView {
+    pub fn new(
+        cx: &mut Context<Self>,
+        client: JellyfinClient,
+        images: ImageService,
+        user_id: Option<String>,
+        library_id: String,
+        on_navigate: impl Fn(Screen, &mut Window, &mut Context<LibraryView>) + 'static,
+        on_back: impl Fn(&mut Window, &mut Context<LibraryView>) + 'static,
+    ) -> Self {
+        let this = Self {
+            client,
+            user_id,
+            library_id,
+            title: String::new(),
+            item_types: None,
+            items: Vec::new(),
+            total: None,
+            generation: 0,
+            is_loading: false,
+            error_message: None,
+            sort: LibrarySort::Name,
+            descending: false,
+            filters: LibraryFilters::default(),
+            available: AvailableFilters::default(),
+            show_filters: false,
+            columns: 1,
+            images,
+            scroll_handle: UniformListScrollHandle::new(),
+            on_navigate: Box::new(on_navigate),
+            on_back: Box::new(on_back),
+        };
+        this.load_library(cx);
+        this
+    }
+
+    /// Looks up the library itself for its name and content type, then loads
+    /// the first page and the filter choices.
+    fn load_library(&self, cx: &mut Context<Self>) {
+        let client = self.client.clone();
+        let library_id = self.library_id.clone();
+        let query = GetItemQuery {
+            user_id: self.user_id.clone(),
+        };
+
+        cx.spawn(async move |this, cx| {
+            let result = client.get_item(&library_id, &query).await;
+            let _ = this.update(cx, |view, cx| {
+                match result {
+                    Ok(library) => {
+                        view.title = library.name.unwrap_or_default();
+                        view.item_types = library.collection_type.and_then(item_types_for);
+                    }
+                    Err(e) => tracing::warn!("Failed to load library {}: {}", view.library_id, e),
+                }
+                view.load_filters(cx);
+                view.load_more(cx);
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Genres come from `/Items/Filters2`, which has their ids. It doesn't
+    /// list years or ratings, so those come from the older `/Items/Filters`.
+    fn load_filters(&self, cx: &mut Context<Self>) {
+        let client = self.client.clone();
+        let query = GetQueryFiltersQuery {
+            user_id: self.user_id.clone(),
+            parent_id: Some(self.library_id.clone()),
+            include_item_types: self.item_types.clone(),
+            recursive: Some(true),
+            ..Default::default()
+        };
+        let legacy_query = GetQueryFiltersLegacyQuery {
+            user_id: self.user_id.clone(),
+            parent_id: Some(self.library_id.clone()),
+            include_item_types: self.item_types.clone(),
+            ..Default::default()
+        };
+
+        cx.spawn(async move |this, cx| {
+            let (filters, legacy) =
+                tokio::join!(client.get_query_filters(&query), client.get_query_filters_legacy(&legacy_query));
+
+            let mut available = AvailableFilters::default();
+            match filters {
+                Ok(filters) => available.genres = filters.genres.unwrap_or_default(),
+                Err(e) => tracing::warn!("Failed to load genre filters: {}", e),
+            }
+            match legacy {
+                Ok(legacy) => {
+                    let mut years = legacy.years.unwrap_or_default();
+                    years.sort_unstable_by(|a, b| b.cmp(a));
+                    available.years = years;
+                    available.official_ratings = legacy.official_ratings.unwrap_or_default();
+                }
+                Err(e) => tracing::warn!("Failed to load year and rating filters: {}", e),
+            }
+
+            let _ = this.update(cx, |view, cx| {
+                view.available = available;
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn items_query(&self, start_index: usize) -> GetItemsQuery {
+        let sort_order = if self.descending { SortOrder::Descending } else { SortOrder::Ascending };
+        let non_empty = |set: &BTreeSet<String>| (!set.is_empty()).then(|| set.iter().cloned().collect());
+
+        GetItemsQuery {
+            user_id: self.user_id.clone(),
+            parent_id: Some(self.library_id.clone()),
+            include_item_types: self.item_types.clone(),
+            recursive: Some(self.item_types.is_some()),
+            start_index: Some(start_index as i32),
+            limit: Some(PAGE_SIZE),
+            sort_by: Some(self.sort.sort_by()),
+            sort_order: Some(vec![sort_order]),
+            filters: self.filters.item_filters(),
+            genre_ids: non_empty(&self.filters.genre_ids),
+            official_ratings: non_empty(&self.filters.official_ratings),
+            years: (!self.filters.years.is_empty()).then(|| self.filters.years.iter().copied().collect()),
+            fields: Some(vec![ItemFields::PrimaryImageAspectRatio]),
+            enable_user_data: Some(true),
+            enable_total_record_count: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn has_more(&self) -> bool {
+        self.total.is_none_or(|total| self.items.len() < total)
+    }
+
+    /// Fetches the next page unless one is already on its way.
+    fn load_more(&mut self, cx: &mut Context<Self>) {
+        if self.is_loading || !self.has_more() {
+            return;
+        }
+        self.is_loading = true;
+
+        let client = self.client.clone();
+        let query = self.items_query(self.items.len());
+        let generation = self.generation;
+        cx.spawn(async move |this, cx| {
+            let result = client.get_items(&query).await;
+            let _ = this.update(cx, |view, cx| {
+                if view.generation != generation {
+                    return;
+                }
+                view.is_loading = false;
+                match result {
+                    Ok(page) => {
+                        let items = page.items.unwrap_or_default();
+                        // A short page means the end, whatever the count said.
+                        view.total = match page.total_record_count {
+                            Some(total) if items.len() as i32 == PAGE_SIZE => Some(total.max(0) as usize),
+                            _ => Some(view.items.len() + items.len()),
+                        };
+                        view.items.extend(items);
+                        view.error_message = None;
+                    }
+                    Err(e) => view.error_message = Some(describe_error(&e)),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Starts over from the first page after sorting or filters changed.
+    fn reload(&mut self, cx: &mut Context<Self>) {
+        self.generation += 1;
+        self.items.clear();
+        self.total = None;
+        self.is_loading = false;
+        self.error_message = None;
+        self.scroll_handle.scroll_to_item(0, ScrollStrategy::Top);
+        self.load_more(cx);
+        cx.notify();
+    }
+
+    fn set_sort(&mut self, sort: LibrarySort, cx: &mut Context<Self>) {
+        if self.sort == sort {
+            self.descending = !self.descending;
+        } else {
+            self.sort = sort;
+            self.descending = sort.default_descending();
+        }
+        self.reload(cx);
+    }
+
+    fn update_filters(&mut self, cx: &mut Context<Self>, f: impl FnOnce(&mut LibraryFilters)) {
+        f(&mut self.filters);
+        self.reload(cx);
+    }
+
+    /// Clears a failed page's error and asks for it again. Until then the
+    /// grid doesn't fetch on its own, so an unreachable server isn't asked
+    /// again every frame.
+    fn retry(&mut self, cx: &mut Context<Self>) {
+        self.error_message = None;
+        self.load_more(cx);
+        cx.notify();
+    }
+
+    /// Builds the grid rows in `rows`. Only visible rows are built, so only
+    /// their posters are requested; the next page is fetched once the last
+    /// loaded row comes into view, after the frame is laid out.
+    fn render_rows(&mut self, rows: Range<usize>, cx: &mut Context<Self>) -> Vec<AnyElement> {
+        let columns = self.columns.max(1);
+        let total_rows = self.items.len().div_ceil(columns);
+        if rows.end + 2 >= total_rows && self.error_message.is_none() && !self.is_loading && self.has_more() {
+            cx.defer(|this, cx| this.load_more(cx));
+        }
+
+        rows.map(|row| {
+            let start = row * columns;
+            let end = (start + columns).min(self.items.len());
+            h_flex()
+                .px(GRID_PADDING)
+                .pb(GRID_GAP)
+                .gap(GRID_GAP)
+                .items_start()
+                .children(self.items[start..end].iter().enumerate().map(|(i, item)| {
+                    let image = self.images.image(Artwork::portrait(item).as_ref(), PosterCard::PORTRAIT.width, cx);
+                    let progress = item
+                        .user_data
+                        .as_ref()
+                        .and_then(|data| data.played_percentage)
+                        .map(|p| (p / 100.) as f32);
+                    let subtitle = item.production_year.map(|year| year.to_string());
+
+                    PosterCard::new(("library_card", start + i), item.name.clone().unwrap_or_default(), image)
+                        .subtitle(subtitle)
+                        .progress(progress)
+                        .when_some(item.id.clone(), |card, id| {
+                            card.on_click(cx.listener(move |this, _, window, cx| {
+                                (this.on_navigate)(Screen::Item(id.clone()), window, cx);
+                            }))
+                        })
+                }))
+                .into_any_element()
+        })
+        .collect()
+    }
+
+    fn render_toolbar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let played = self.filters.played_filter();
+
+        h_flex()
+            .px(GRID_PADDING)
+            .gap_1()
+            .flex_wrap()
+            .items_center()
+            .children(LibrarySort::ALL.into_iter().map(|sort| {
+                let active = self.sort == sort;
+                let label = match (active, self.descending) {
+                    (true, true) => format!("{} ↓", sort.label()),
+                    (true, false) => format!("{} ↑", sort.label()),
+                    (false, _) => sort.label().to_string(),
+                };
+                chip(("sort", sort as usize), label, active)
+                    .on_click(cx.listener(move |this, _, _window, cx| this.set_sort(sort, cx)))
+            }))
+            .child(div().w_4())
+            .children([PlayedFilter::All, PlayedFilter::Unplayed, PlayedFilter::Played].into_iter().map(|filter| {
+                let (label, value) = match filter {
+                    PlayedFilter::All => ("All", None),
+                    PlayedFilter::Unplayed => ("Unplayed", Some(false)),
+                    PlayedFilter::Played => ("Played", Some(true)),
+                };
+                chip(("played", filter as usize), label.to_string(), played == filter)
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.update_filters(cx, |filters| filters.played = value);
+                    }))
+            }))
+            .child(
+                chip("favorites", "Favorites".to_string(), self.filters.favorites).on_click(cx.listener(
+                    |this, _, _window, cx| this.update_filters(cx, |filters| filters.favorites = !filters.favorites),
+                )),
+            )
+            .child(
+                chip("more_filters", "Filters".to_string(), self.show_filters).on_click(cx.listener(
+                    |this, _, _window, cx| {
+                        this.show_filters = !this.show_filters;
+                        cx.notify();
+                    },
+                )),
+            )
+    }
+
+    fn render_filter_panel(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let section = |title: &'static str| div().text_sm().font_bold().text_color(theme.muted_foreground).child(title);
+
+        v_flex()
+            .id("filter_panel")
+            .mx(GRID_PADDING)
+            .p_3()
+            .gap_2()
+            .max_h_64()
+            .overflow_y_scroll()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_md()
+            .when(!self.available.genres.is_empty(), |this| {
+                this.child(section("Genres")).child(h_flex().flex_wrap().gap_1().children(
+                    self.available.genres.iter().enumerate().filter_map(|(i, genre)| {
+                        let id = genre.id.clone()?;
+                        let active = self.filters.genre_ids.contains(&id);
+                        Some(chip(("genre", i), genre.name.clone().unwrap_or_default(), active).on_click(
+                            cx.listener(move |this, _, _window, cx| {
+                                let id = id.clone();
+                                this.update_filters(cx, |filters| toggle(&mut filters.genre_ids, id));
+                            }),
+                        ))
+                    }),
+                ))
+            })
+            .when(!self.available.years.is_empty(), |this| {
+                this.child(section("Years")).child(h_flex().flex_wrap().gap_1().children(
+                    self.available.years.iter().map(|&year| {
+                        let active = self.filters.years.contains(&year);
+                        chip(("year", year as usize), year.to_string(), active).on_click(cx.listener(
+                            move |this, _, _window, cx| {
+                                this.update_filters(cx, |filters| toggle(&mut filters.years, year));
+                            },
+                        ))
+                    }),
+                ))
+            })
+            .when(!self.available.official_ratings.is_empty(), |this| {
+                this.child(section("Parental Rating")).child(h_flex().flex_wrap().gap_1().children(
+                    self.available.official_ratings.iter().enumerate().map(|(i, rating)| {
+                        let active = self.filters.official_ratings.contains(rating);
+                        let rating = rating.clone();
+                        chip(("rating", i), rating.clone(), active).on_click(cx.listener(
+                            move |this, _, _window, cx| {
+                                let rating = rating.clone();
+                                this.update_filters(cx, |filters| toggle(&mut filters.official_ratings, rating));
+                            },
+                        ))
+                    }),
+                ))
+            })
+            .when(self.filters != LibraryFilters::default(), |this| {
+                this.child(
+                    Button::new("clear_filters")
+                        .ghost()
+                        .label("Clear Filters")
+                        .on_click(cx.listener(|this, _, _window, cx| {
+                            this.update_filters(cx, |filters| *filters = LibraryFilters::default());
+                        })),
+                )
+            })
+    }
+}
+
+/// What a library of each kind lists at its top level.
+fn item_types_for(collection_type: CollectionType) -> Option<Vec<BaseItemKind>> {
+    match collection_type {
+        CollectionType::Movies => Some(vec![BaseItemKind::Movie]),
+        CollectionType::Tvshows => Some(vec![BaseItemKind::Series]),
+        CollectionType::Music => Some(vec![BaseItemKind::MusicAlbum]),
+        CollectionType::Musicvideos => Some(vec![BaseItemKind::MusicVideo]),
+        CollectionType::Boxsets => Some(vec![BaseItemKind::BoxSet]),
+        CollectionType::Books => Some(vec![BaseItemKind::Book]),
+        _ => None,
+    }
+}
+
+fn toggle<T: Ord>(set: &mut BTreeSet<T>, value: T) {
+    if !set.remove(&value) {
+        set.insert(value);
+    }
+}
+
+/// A small toggle button for the sort and filter bars.
+fn chip(id: impl Into<ElementId>, label: String, active: bool) -> Button {
+    let button = Button::new(id).label(label).small();
+    if active { button.primary() } else { button.ghost() }
+}
+
+fn describe_error(error: &ApiError) -> String {
+    match error {
+        ApiError::Connect(_) | ApiError::Timeout(_) => {
+            "Could not reach the server. Check your connection and try again.".to_string()
+        }
+        other => format!("Failed to load this library: {}", other),
+    }
+}
+
+impl Render for LibraryView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let card_width = PosterCard::PORTRAIT.width;
+        let available = window.viewport_size().width - GRID_PADDING * 2.;
+        self.columns = (((available + GRID_GAP) / (card_width + GRID_GAP)).floor() as usize).max(1);
+        let row_count = self.items.len().div_ceil(self.columns);
+
+        let toolbar = self.render_toolbar(cx).into_any_element();
+        let filter_panel = self.show_filters.then(|| self.render_filter_panel(cx).into_any_element());
+        let count = match self.total {
+            Some(total) => format!("{} items", total),
+            None => String::new(),
+        };
+        let theme = cx.theme();
+
+        v_flex()
+            .size_full()
+            .bg(theme.background)
+            .child(
+                h_flex()
+                    .p_4()
+                    .gap_3()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(theme.border)
+                    .child(
+                        Button::new("back")
+                            .ghost()
+                            .icon(IconName::ArrowLeft)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                (this.on_back)(window, cx);
+                            }))
+                    )
+                    .child(div().text_xl().font_bold().child(self.title.clone()))
+                    .child(div().text_sm().text_color(theme.muted_foreground).child(count))
+            )
+            .child(v_flex().py_3().gap_3().child(toolbar).children(filter_panel))
+            .when_some(self.error_message.clone(), |this, message| {
+                this.child(
+                    h_flex()
+                        .px(GRID_PADDING)
+                        .pb_3()
+                        .gap_2()
+                        .items_center()
+                        .child(div().text_sm().text_color(theme.danger).child(message))
+                        .child(
+                            Button::new("retry")
+                                .label("Retry")
+                                .on_click(cx.listener(|this, _, _window, cx| this.retry(cx)))
+                        )
+                )
+            })
+            .when(!self.is_loading && self.items.is_empty() && self.error_message.is_none(), |this| {
+                this.child(
+                    div()
+                        .px(GRID_PADDING)
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child(if self.total.is_some() { "Nothing matches these filters." } else { "Loading..." })
+                )
+            })
+            .child(
+                uniform_list("library_grid", row_count, cx.processor(|this, rows, _window, cx| this.render_rows(rows, cx)))
+                    .flex_1()
+                    .track_scroll(self.scroll_handle.clone())
+            )
+    }
+}
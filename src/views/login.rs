@@ -1,17 +1,26 @@
-use crate::components::PasswordInput;
+use crate::api::{ApiError, UserDto};
+use crate::components::{Avatar, PasswordInput};
 use gpui::prelude::*;
 use gpui::*;
 use gpui_component::{button::{Button, ButtonVariants}, input::{Input, InputState}, *};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 pub struct LoginView {
     username_input: Entity<InputState>,
     password_input: Entity<InputState>,
     server_url: String,
     on_login: Box<dyn Fn(String, String, &mut Window, &mut Context<LoginView>) + 'static>,
+    on_quick_connect: Box<dyn Fn(&mut Window, &mut Context<LoginView>) + 'static>,
     on_back: Box<dyn Fn(&mut Window, &mut Context<LoginView>) + 'static>,
     is_loading: bool,
     error_message: Option<String>,
     is_password_visible: bool,
+    quick_connect_available: bool,
+    quick_connect_code: Option<String>,
+    public_users: Vec<UserDto>,
+    public_avatars: HashMap<String, Arc<Image>>,
+    selected_user_id: Option<String>,
 }
 
 impl LoginView {
@@ -20,6 +29,7 @@ impl LoginView {
         cx: &mut Context<Self>,
         server_url: String,
         on_login: impl Fn(String, String, &mut Window, &mut Context<LoginView>) + 'static,
+        on_quick_connect: impl Fn(&mut Window, &mut Context<LoginView>) + 'static,
         on_back: impl Fn(&mut Window, &mut Context<LoginView>) + 'static,
     ) -> Self {
         let username_input = cx.new(|cx| InputState::new(window, cx).placeholder("Username"));
@@ -30,13 +40,60 @@ impl LoginView {
             password_input,
             server_url,
             on_login: Box::new(on_login),
+            on_quick_connect: Box::new(on_quick_connect),
             on_back: Box::new(on_back),
             is_loading: false,
             error_message: None,
             is_password_visible: false,
+            quick_connect_available: false,
+            quick_connect_code: None,
+            public_users: Vec::new(),
+            public_avatars: HashMap::new(),
+            selected_user_id: None,
         }
     }
 
+    /// Shows the users the server lists publicly as tiles above the manual
+    /// fields. Servers that hide the list leave only the fields.
+    pub fn set_public_users(&mut self, users: Vec<UserDto>, cx: &mut Context<Self>) {
+        self.public_users = users.into_iter().filter(|u| u.id.is_some() && u.name.is_some()).collect();
+        cx.notify();
+    }
+
+    pub fn set_public_user_avatar(&mut self, user_id: String, image: Arc<Image>, cx: &mut Context<Self>) {
+        self.public_avatars.insert(user_id, image);
+        cx.notify();
+    }
+
+    /// Prefills the username from a tile. Users without a password are signed
+    /// in straight away; everyone else is asked for theirs.
+    fn select_public_user(&mut self, user: &UserDto, window: &mut Window, cx: &mut Context<Self>) {
+        let name = user.name.clone().unwrap_or_default();
+        self.username_input.update(cx, |state, cx| state.set_value(name.clone(), window, cx));
+        self.password_input.update(cx, |state, cx| state.set_value("", window, cx));
+        self.selected_user_id = user.id.clone();
+        cx.notify();
+
+        if user.has_password == Some(false) {
+            (self.on_login)(name, String::new(), window, cx);
+        } else {
+            let handle = self.password_input.focus_handle(cx);
+            window.focus(&handle);
+        }
+    }
+
+    /// Fills in the username of an account that has to sign in again and
+    /// moves on to its password.
+    pub fn prefill_username(&mut self, name: String, window: &mut Window, cx: &mut Context<Self>) {
+        if name.is_empty() {
+            return;
+        }
+        self.username_input.update(cx, |state, cx| state.set_value(name, window, cx));
+        let handle = self.password_input.focus_handle(cx);
+        window.focus(&handle);
+        cx.notify();
+    }
+
     pub fn set_loading(&mut self, loading: bool, cx: &mut Context<Self>) {
         self.is_loading = loading;
         cx.notify();
@@ -46,6 +103,54 @@ impl LoginView {
         self.error_message = error;
         cx.notify();
     }
+
+    pub fn set_api_error(&mut self, error: &ApiError, cx: &mut Context<Self>) {
+        self.set_error(Some(Self::describe_error(error)), cx);
+    }
+
+    pub fn set_quick_connect_available(&mut self, available: bool, cx: &mut Context<Self>) {
+        self.quick_connect_available = available;
+        cx.notify();
+    }
+
+    /// Shows the code the user has to approve on another device, or hides it.
+    /// Clearing the code is also how a pending request gets cancelled.
+    pub fn set_quick_connect_code(&mut self, code: Option<String>, cx: &mut Context<Self>) {
+        self.quick_connect_code = code;
+        cx.notify();
+    }
+
+    pub fn quick_connect_code(&self) -> Option<&str> {
+        self.quick_connect_code.as_deref()
+    }
+
+    pub fn set_quick_connect_error(&mut self, error: &ApiError, cx: &mut Context<Self>) {
+        let message = match error {
+            ApiError::Unauthorized => "Quick Connect is not enabled on this server.".to_string(),
+            ApiError::NotFound => "The Quick Connect code expired. Try again.".to_string(),
+            other => Self::describe_error(other),
+        };
+        self.set_error(Some(message), cx);
+    }
+
+    fn describe_error(error: &ApiError) -> String {
+        match error {
+            ApiError::Unauthorized => "Incorrect username or password.".to_string(),
+            ApiError::Forbidden => {
+                "This account is disabled or not allowed to sign in. Contact your server administrator.".to_string()
+            }
+            ApiError::Connect(_) | ApiError::Timeout(_) => {
+                "Could not reach the server. Check your connection and try again.".to_string()
+            }
+            ApiError::Tls(_) => {
+                "Secure connection failed. The server's certificate may be self-signed or expired.".to_string()
+            }
+            ApiError::Server(status) => {
+                format!("The server reported an error ({}). Try again later.", status)
+            }
+            other => format!("Login failed: {}", other),
+        }
+    }
 }
 
 impl Render for LoginView {
@@ -68,24 +173,77 @@ impl Render for LoginView {
                     .gap_4()
                     .child(div().text_xl().font_bold().child("Login"))
                     .child(div().text_sm().text_color(theme.muted_foreground).child(self.server_url.clone()))
-                    .child(Input::new(&self.username_input).disabled(self.is_loading))
-                    .child(
-                        PasswordInput::new(&self.password_input, is_masked)
-                            .on_toggle(cx.listener(|this, _, window, cx| {
-                                this.is_password_visible = !this.is_password_visible;
-                                let is_masked = !this.is_password_visible;
-
-                                // We need to update the InputState.
-                                // set_masked requires &mut Window.
-                                // We capture window from the listener scope.
-                                // Note: This might conflict with cx borrow if update borrows something that overlaps with window.
-                                // But in GPUI 0.2, Window and App (Context) are separate.
-                                this.password_input.update(cx, |state, cx| {
-                                    state.set_masked(is_masked, window, cx);
-                                });
-                                cx.notify();
-                            }))
-                    )
+                    .when_some(self.quick_connect_code.clone(), |this, code| {
+                        this.child(
+                            v_flex()
+                                .gap_2()
+                                .items_center()
+                                .p_4()
+                                .border_1()
+                                .border_color(theme.border)
+                                .rounded_md()
+                                .child(div().text_sm().text_color(theme.muted_foreground).child("Your Quick Connect code"))
+                                .child(div().text_3xl().font_bold().child(code))
+                                .child(
+                                    div()
+                                        .text_sm()
+                                        .text_color(theme.muted_foreground)
+                                        .child("Enter it under Quick Connect in another signed-in Jellyfin app. Waiting for approval...")
+                                )
+                        )
+                    })
+                    .when(self.quick_connect_code.is_none() && !self.public_users.is_empty(), |this| {
+                        this.child(
+                            h_flex()
+                                .flex_wrap()
+                                .gap_2()
+                                .children(self.public_users.iter().enumerate().map(|(i, user)| {
+                                    let user_id = user.id.clone().unwrap_or_default();
+                                    let name = user.name.clone().unwrap_or_default();
+                                    let is_selected = self.selected_user_id.as_deref() == Some(user_id.as_str());
+                                    let user = user.clone();
+                                    v_flex()
+                                        .id(("public_user", i))
+                                        .w_20()
+                                        .p_2()
+                                        .gap_1()
+                                        .items_center()
+                                        .rounded_md()
+                                        .border_1()
+                                        .border_color(if is_selected { theme.border } else { transparent_black() })
+                                        .cursor_pointer()
+                                        .hover(|s| s.bg(theme.list_hover))
+                                        .child(Avatar::new(name.clone(), self.public_avatars.get(&user_id).cloned()).with_size(px(48.)))
+                                        .child(div().text_xs().text_center().truncate().w_full().child(name))
+                                        .when(!self.is_loading, |this| {
+                                            this.on_click(cx.listener(move |this, _, window, cx| {
+                                                this.select_public_user(&user, window, cx);
+                                            }))
+                                        })
+                                }))
+                        )
+                    })
+                    .when(self.quick_connect_code.is_none(), |this| {
+                        this
+                            .child(Input::new(&self.username_input).disabled(self.is_loading))
+                            .child(
+                                PasswordInput::new(&self.password_input, is_masked)
+                                    .on_toggle(cx.listener(|this, _, window, cx| {
+                                        this.is_password_visible = !this.is_password_visible;
+                                        let is_masked = !this.is_password_visible;
+
+                                        // We need to update the InputState.
+                                        // set_masked requires &mut Window.
+                                        // We capture window from the listener scope.
+                                        // Note: This might conflict with cx borrow if update borrows something that overlaps with window.
+                                        // But in GPUI 0.2, Window and App (Context) are separate.
+                                        this.password_input.update(cx, |state, cx| {
+                                            state.set_masked(is_masked, window, cx);
+                                        });
+                                        cx.notify();
+                                    }))
+                            )
+                    })
                     .children(self.error_message.as_ref().map(|msg| {
                         div().text_sm().text_color(theme.danger).child(msg.clone())
                     }))
@@ -95,12 +253,28 @@ impl Render for LoginView {
                             .justify_end()
                             .child(
                                 Button::new("back")
-                                    .label("Back")
-                                    .disabled(self.is_loading)
+                                    .label(if self.quick_connect_code.is_some() { "Cancel" } else { "Back" })
+                                    .disabled(self.is_loading && self.quick_connect_code.is_none())
                                     .on_click(cx.listener(|this, _, window, cx| {
+                                        if this.quick_connect_code.is_some() {
+                                            this.quick_connect_code = None;
+                                            this.is_loading = false;
+                                            cx.notify();
+                                            return;
+                                        }
                                         (this.on_back)(window, cx);
                                     }))
                             )
+                            .when(self.quick_connect_available && self.quick_connect_code.is_none(), |this| {
+                                this.child(
+                                    Button::new("quick_connect")
+                                        .label("Quick Connect")
+                                        .disabled(self.is_loading)
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            (this.on_quick_connect)(window, cx);
+                                        }))
+                                )
+                            })
                             .child(
                                 Button::new("login")
                                     .primary()
@@ -1,7 +1,21 @@
 pub mod add_server;
+pub mod home;
+pub mod item;
+pub mod library;
 pub mod login;
+pub mod player;
+pub mod search;
 pub mod server_list;
+pub mod settings;
+pub mod user_picker;
 
 pub use add_server::AddServerView;
+pub use home::HomeView;
+pub use item::ItemView;
+pub use library::LibraryView;
 pub use login::LoginView;
+pub use player::PlayerView;
+pub use search::SearchView;
 pub use server_list::ServerListView;
+pub use settings::SettingsView;
+pub use user_picker::UserPickerView;
@@ -0,0 +1,1111 @@
+use crate::api::{ApiError, BaseItemDto, BaseItemKind, GetItemQuery, JellyfinClient, PlayMethod};
+use crate::config::SubtitleStyle;
+use crate::player::negotiation::{negotiate, PlaybackError, PlaybackRequest, StreamChoice, StreamDelivery};
+use crate::player::reporting::{PlaybackReporter, PlaybackSnapshot};
+use crate::player::subtitles::{burn_in, fetch_subtitles, subtitle_tracks, SubtitleTrack, Subtitles};
+use crate::player::tracks::{audio_tracks, mpv_audio_id, AudioTrack, LanguagePreferences};
+use crate::player::{Player, PlayerEvent};
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::{button::{Button, ButtonVariants}, *};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Jellyfin measures time in 100ns ticks.
+const TICKS_PER_SECOND: f64 = 10_000_000.;
+
+/// How long the controls stay up after the mouse last moved.
+const CONTROLS_TIMEOUT: Duration = Duration::from_secs(3);
+/// How often the server hears where playback is while nothing else happens.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+/// How long a click waits for a second one before it toggles pause, so that
+/// double-clicking for fullscreen doesn't also pause.
+const DOUBLE_CLICK_WAIT: Duration = Duration::from_millis(300);
+const SEEK_STEP: f64 = 10.;
+const VOLUME_STEP: f64 = 5.;
+const SUBTITLE_OFFSET_STEP: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackMenu {
+    Audio,
+    Subtitles,
+}
+
+/// Plays a video full-window with an on-screen control overlay.
+pub struct PlayerView {
+    client: JellyfinClient,
+    reporter: PlaybackReporter,
+    user_id: Option<String>,
+    item_id: String,
+    item: Option<BaseItemDto>,
+    stream: Option<StreamChoice>,
+    audio_stream_index: Option<i32>,
+    subtitle_stream_index: Option<i32>,
+    /// Set once the server has been told playback started, so that it's
+    /// also told when it stops.
+    reported_start: bool,
+    last_progress_report: Option<Instant>,
+    /// Reports the position the player lands on after a seek.
+    report_next_position: bool,
+    failed: bool,
+    /// Where a restart that failed was to carry on from, so that Retry
+    /// restarts there rather than going back to the resume position.
+    retry_position: Option<f64>,
+    audio_tracks: Vec<AudioTrack>,
+    subtitle_tracks: Vec<SubtitleTrack>,
+    /// The chosen text track's cues once fetched.
+    subtitles: Option<Subtitles>,
+    /// Fetches the chosen track. Replacing it abandons the last choice.
+    subtitle_task: Option<Task<()>>,
+    subtitle_style: SubtitleStyle,
+    subtitle_color: Hsla,
+    /// Starts at the configured offset; adjusting it only lasts this playback.
+    subtitle_offset: f64,
+    open_menu: Option<TrackMenu>,
+    /// `None` when libmpv couldn't be started.
+    player: Option<Player>,
+    frame: Option<Arc<RenderImage>>,
+    position: f64,
+    duration: f64,
+    paused: bool,
+    volume: f64,
+    muted: bool,
+    buffering: bool,
+    is_loading: bool,
+    error_message: Option<String>,
+    controls_visible: bool,
+    /// Hides the controls once it fires. Replacing it restarts the timeout.
+    hide_controls_task: Option<Task<()>>,
+    /// Toggles pause once a click on the video turns out not to be the start
+    /// of a double-click. Dropping it cancels the toggle.
+    pending_click_task: Option<Task<()>>,
+    seek_bar_bounds: Rc<Cell<Bounds<Pixels>>>,
+    volume_bar_bounds: Rc<Cell<Bounds<Pixels>>>,
+    focus_handle: FocusHandle,
+    on_back: Box<dyn Fn(&mut Window, &mut Context<PlayerView>) + 'static>,
+}
+
+impl PlayerView {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        client: JellyfinClient,
+        reporter: PlaybackReporter,
+        user_id: Option<String>,
+        item_id: String,
+        subtitle_style: SubtitleStyle,
+        on_back: impl Fn(&mut Window, &mut Context<PlayerView>) + 'static,
+    ) -> Self {
+        let focus_handle = cx.focus_handle();
+        window.focus(&focus_handle);
+
+        let started = Player::new().and_then(|(player, events)| {
+            // mpv fetches the stream itself, as this session.
+            player.add_http_header("Authorization", &client.auth_header())?;
+            Ok((player, events))
+        });
+        let (player, error_message) = match started {
+            Ok((player, events)) => {
+                Self::pump_events(events, window, cx);
+                (Some(player), None)
+            }
+            Err(e) => {
+                tracing::error!("Failed to start the player: {:#}", e);
+                (None, Some(format!("Playback isn't available: {:#}", e)))
+            }
+        };
+
+        cx.on_release(|view, _cx| view.report_stopped()).detach();
+
+        let subtitle_color = Rgba::try_from(subtitle_style.color.as_str()).map_or_else(
+            |e| {
+                tracing::warn!("Invalid subtitle color {:?}: {}", subtitle_style.color, e);
+                white()
+            },
+            Hsla::from,
+        );
+
+        let mut this = Self {
+            client,
+            reporter,
+            user_id,
+            item_id,
+            item: None,
+            stream: None,
+            audio_stream_index: None,
+            subtitle_stream_index: None,
+            reported_start: false,
+            last_progress_report: None,
+            report_next_position: false,
+            failed: false,
+            retry_position: None,
+            audio_tracks: Vec::new(),
+            subtitle_tracks: Vec::new(),
+            subtitles: None,
+            subtitle_task: None,
+            subtitle_offset: subtitle_style.offset,
+            subtitle_style,
+            subtitle_color,
+            open_menu: None,
+            player,
+            frame: None,
+            position: 0.,
+            duration: 0.,
+            paused: false,
+            volume: 100.,
+            muted: false,
+            buffering: false,
+            is_loading: false,
+            error_message,
+            controls_visible: true,
+            hide_controls_task: None,
+            pending_click_task: None,
+            seek_bar_bounds: Rc::new(Cell::new(Bounds::default())),
+            volume_bar_bounds: Rc::new(Cell::new(Bounds::default())),
+            focus_handle,
+            on_back: Box::new(on_back),
+        };
+        if this.player.is_some() {
+            this.load(cx);
+        }
+        this
+    }
+
+    /// Hands the player's events to the view until either goes away.
+    fn pump_events(mut events: UnboundedReceiver<PlayerEvent>, window: &mut Window, cx: &mut Context<Self>) {
+        cx.spawn_in(window, async move |this, cx| {
+            while let Some(event) = events.recv().await {
+                if this.update_in(cx, |view, window, cx| view.handle_event(event, window, cx)).is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Fetches the item for its title and resume position, agrees on a
+    /// stream with the server, then starts it with the tracks the user's
+    /// language preferences call for.
+    fn load(&mut self, cx: &mut Context<Self>) {
+        if self.is_loading {
+            return;
+        }
+        self.is_loading = true;
+        self.error_message = None;
+        self.retry_position = None;
+        cx.notify();
+
+        let client = self.client.clone();
+        let item_id = self.item_id.clone();
+        let query = GetItemQuery {
+            user_id: self.user_id.clone(),
+        };
+        let user_id = self.user_id.clone();
+        cx.spawn(async move |this, cx| {
+            let result = async {
+                let item = client.get_item(&item_id, &query).await?;
+                // Fetched each time, so changes made on other devices count.
+                let preferences = match &user_id {
+                    Some(user_id) => LanguagePreferences::from_user(&client.get_user_by_id(user_id).await?),
+                    None => LanguagePreferences::default(),
+                };
+                let request = PlaybackRequest {
+                    user_id: user_id.clone(),
+                    ..Default::default()
+                };
+                let stream = negotiate(&client, &item_id, &request).await?;
+
+                // Only the server knows which version it will play, so the
+                // tracks are picked from its answer. It picks by the same
+                // preferences, so a second round is rarely needed.
+                let audio_tracks = audio_tracks(&stream.media_source);
+                let audio = preferences.audio_stream(&audio_tracks);
+                let audio_language = audio_tracks
+                    .iter()
+                    .find(|track| Some(track.index) == audio)
+                    .and_then(|track| track.language.as_deref());
+                let subtitle_tracks = subtitle_tracks(&stream.media_source);
+                let subtitle = preferences.subtitle_stream(&subtitle_tracks, audio_language);
+                let stream = if stream.delivers(audio, burn_in(&subtitle_tracks, subtitle)) {
+                    stream
+                } else {
+                    let request = stream_request(user_id, &stream, audio, subtitle);
+                    negotiate(&client, &item_id, &request).await?
+                };
+                Ok::<_, PlaybackError>((item, stream, audio, subtitle))
+            }
+            .await;
+            let _ = this.update(cx, |view, cx| {
+                view.is_loading = false;
+                match result {
+                    Ok((item, stream, audio, subtitle)) => {
+                        // Picks up where the user left off.
+                        let start = item
+                            .user_data
+                            .as_ref()
+                            .and_then(|data| data.playback_position_ticks)
+                            .map_or(0., |ticks| ticks as f64 / TICKS_PER_SECOND);
+                        view.audio_stream_index = audio;
+                        view.subtitle_stream_index = subtitle;
+                        view.item = Some(item);
+                        view.play(stream, start, cx);
+                    }
+                    Err(e) => view.error_message = Some(describe_error(&e)),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Agrees on a stream again with the chosen audio and subtitle streams,
+    /// e.g. to have the server transcode another audio stream or burn in a
+    /// subtitle, and carries on from `position`.
+    fn restart_stream(&mut self, position: f64, cx: &mut Context<Self>) {
+        let Some(stream) = &self.stream else { return };
+        if self.is_loading {
+            return;
+        }
+        self.is_loading = true;
+        self.error_message = None;
+        self.retry_position = None;
+        cx.notify();
+
+        let client = self.client.clone();
+        let item_id = self.item_id.clone();
+        let request = stream_request(
+            self.user_id.clone(),
+            stream,
+            self.audio_stream_index,
+            self.subtitle_stream_index,
+        );
+        cx.spawn(async move |this, cx| {
+            let result = negotiate(&client, &item_id, &request).await;
+            let _ = this.update(cx, |view, cx| {
+                view.is_loading = false;
+                match result {
+                    Ok(stream) => view.play(stream, position, cx),
+                    Err(e) => {
+                        view.retry_position = Some(position);
+                        view.error_message = Some(describe_error(&e));
+                    }
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Tries again after a failure: the restart that failed, with the
+    /// tracks and position it was for, or else loading from the start.
+    fn retry(&mut self, cx: &mut Context<Self>) {
+        match self.retry_position {
+            Some(position) if self.stream.is_some() => self.restart_stream(position, cx),
+            _ => self.load(cx),
+        }
+    }
+
+    /// Starts a negotiated stream `start` seconds in, ending the playback
+    /// it replaces, if any.
+    fn play(&mut self, stream: StreamChoice, start: f64, cx: &mut Context<Self>) {
+        // The server stops a session's transcode once it hears it ended.
+        self.report_stopped();
+        self.failed = false;
+        self.audio_tracks = audio_tracks(&stream.media_source);
+        self.subtitle_tracks = subtitle_tracks(&stream.media_source);
+        self.stream = Some(stream);
+
+        let Some(player) = &self.player else { return };
+        let Some(stream) = &self.stream else { return };
+        let audio_id = self.mpv_audio_id();
+        let result = stream.url(&self.client, &self.item_id).map_err(anyhow::Error::from).and_then(|url| {
+            player.set_audio_track(audio_id)?;
+            player.load(url.as_str(), start)
+        });
+        match result {
+            Ok(()) => self.position = start,
+            Err(e) => self.error_message = Some(format!("Failed to start playback: {:#}", e)),
+        }
+        self.load_subtitles(cx);
+    }
+
+    /// Fetches the chosen subtitle track if it's drawn here rather than
+    /// burned in, letting go of the last one.
+    fn load_subtitles(&mut self, cx: &mut Context<Self>) {
+        self.subtitles = None;
+        self.subtitle_task = None;
+        let (Some(stream), Some(index)) = (&self.stream, self.subtitle_stream_index) else { return };
+        let Some(media_source_id) = stream.media_source.id.clone() else { return };
+        let Some(track) = self.subtitle_tracks.iter().find(|track| track.index == index && track.is_text).cloned()
+        else {
+            return;
+        };
+
+        let client = self.client.clone();
+        let item_id = self.item_id.clone();
+        self.subtitle_task = Some(cx.spawn(async move |this, cx| {
+            let result = fetch_subtitles(&client, &item_id, &media_source_id, &track).await;
+            let _ = this.update(cx, |view, cx| match result {
+                Ok(subtitles) => {
+                    view.subtitles = Some(subtitles);
+                    cx.notify();
+                }
+                Err(e) => tracing::warn!("Failed to load subtitles: {:#}", e),
+            });
+        }));
+    }
+
+    /// mpv's number for the chosen audio track. A transcode has just the
+    /// one, so mpv picks.
+    fn mpv_audio_id(&self) -> Option<i64> {
+        let stream = self.stream.as_ref()?;
+        if matches!(stream.delivery, StreamDelivery::Transcode { .. }) {
+            return None;
+        }
+        mpv_audio_id(&self.audio_tracks, self.audio_stream_index?)
+    }
+
+    /// Whether the chosen tracks take a new stream from the server.
+    fn needs_new_stream(&self) -> bool {
+        let burn_in = burn_in(&self.subtitle_tracks, self.subtitle_stream_index);
+        self.stream
+            .as_ref()
+            .is_some_and(|stream| !stream.delivers(self.audio_stream_index, burn_in))
+    }
+
+    /// Switches to the audio track at `index`, in place when the stream
+    /// has it and otherwise by restarting the transcode with it.
+    fn select_audio(&mut self, index: i32, cx: &mut Context<Self>) {
+        self.open_menu = None;
+        if Some(index) != self.audio_stream_index {
+            self.audio_stream_index = Some(index);
+            if self.needs_new_stream() {
+                self.restart_stream(self.position, cx);
+            } else {
+                let audio_id = self.mpv_audio_id();
+                self.with_player(|player| player.set_audio_track(audio_id));
+                self.report_progress();
+            }
+        }
+        self.show_controls(cx);
+        cx.notify();
+    }
+
+    /// Moves on to the next audio track, back to the first after the last.
+    fn cycle_audio(&mut self, cx: &mut Context<Self>) {
+        let next = self
+            .audio_tracks
+            .iter()
+            .skip_while(|track| Some(track.index) != self.audio_stream_index)
+            .nth(1)
+            .or(self.audio_tracks.first());
+        if let Some(next) = next {
+            self.select_audio(next.index, cx);
+        }
+    }
+
+    /// Switches to the subtitle track at `index`, or turns subtitles off.
+    /// Burning a track in, or no longer, takes a new stream from the server.
+    fn select_subtitle(&mut self, index: Option<i32>, cx: &mut Context<Self>) {
+        self.open_menu = None;
+        if index != self.subtitle_stream_index {
+            self.subtitle_stream_index = index;
+            if self.needs_new_stream() {
+                self.restart_stream(self.position, cx);
+            } else {
+                self.load_subtitles(cx);
+                self.report_progress();
+            }
+        }
+        self.show_controls(cx);
+        cx.notify();
+    }
+
+    /// Moves on to the next subtitle track, or off after the last one.
+    fn cycle_subtitles(&mut self, cx: &mut Context<Self>) {
+        let next = match self.subtitle_stream_index {
+            None => self.subtitle_tracks.first(),
+            Some(current) => self
+                .subtitle_tracks
+                .iter()
+                .skip_while(|track| track.index != current)
+                .nth(1),
+        };
+        self.select_subtitle(next.map(|track| track.index), cx);
+    }
+
+    fn adjust_subtitle_offset(&mut self, seconds: f64, cx: &mut Context<Self>) {
+        // Rounded so repeated steps don't drift off the tenths.
+        self.subtitle_offset = ((self.subtitle_offset + seconds) * 10.).round() / 10.;
+        self.show_controls(cx);
+        cx.notify();
+    }
+
+    /// Where playback stands, for reporting to the server.
+    fn snapshot(&self) -> Option<PlaybackSnapshot> {
+        let stream = self.stream.as_ref()?;
+        Some(PlaybackSnapshot {
+            item_id: self.item_id.clone(),
+            media_source_id: stream.media_source.id.clone(),
+            live_stream_id: stream.media_source.live_stream_id.clone(),
+            play_session_id: stream.play_session_id.clone(),
+            play_method: Some(stream.play_method()),
+            position: self.position,
+            audio_stream_index: self.audio_stream_index,
+            subtitle_stream_index: self.subtitle_stream_index,
+            is_paused: self.paused,
+            is_muted: self.muted,
+            volume: self.volume,
+        })
+    }
+
+    /// Tells the server the current playback ended, if it was told it began.
+    fn report_stopped(&mut self) {
+        if !self.reported_start {
+            return;
+        }
+        if let Some(snapshot) = self.snapshot() {
+            self.reporter.report(snapshot.stopped(self.failed));
+        }
+        self.reported_start = false;
+    }
+
+    fn report_progress(&mut self) {
+        if !self.reported_start {
+            return;
+        }
+        let Some(snapshot) = self.snapshot() else { return };
+        self.reporter.report(snapshot.progress());
+        self.last_progress_report = Some(Instant::now());
+        self.report_next_position = false;
+    }
+
+    fn handle_event(&mut self, event: PlayerEvent, window: &mut Window, cx: &mut Context<Self>) {
+        match event {
+            PlayerEvent::Loaded => {
+                self.error_message = None;
+                if !self.reported_start
+                    && let Some(snapshot) = self.snapshot()
+                {
+                    self.reporter.report(snapshot.start());
+                    self.reported_start = true;
+                    self.last_progress_report = Some(Instant::now());
+                }
+                self.show_controls(cx);
+            }
+            PlayerEvent::Frame => {
+                let Some(frame) = self.player.as_ref().and_then(Player::take_frame) else { return };
+                // Every frame is a new texture; the last one has to be let go.
+                if let Some(old) = self.frame.replace(frame) {
+                    cx.drop_image(old, Some(window));
+                }
+            }
+            PlayerEvent::Position(position) => {
+                self.position = position;
+                let due = self
+                    .last_progress_report
+                    .is_none_or(|reported| reported.elapsed() >= PROGRESS_INTERVAL);
+                if self.report_next_position || due {
+                    self.report_progress();
+                }
+            }
+            PlayerEvent::Duration(duration) => self.duration = duration,
+            PlayerEvent::Paused(paused) => {
+                if paused != self.paused {
+                    self.paused = paused;
+                    self.report_progress();
+                }
+                self.show_controls(cx);
+            }
+            PlayerEvent::Volume(volume) => self.volume = volume,
+            PlayerEvent::Muted(muted) => self.muted = muted,
+            PlayerEvent::Buffering(buffering) => self.buffering = buffering,
+            PlayerEvent::Ended => {
+                (self.on_back)(window, cx);
+                return;
+            }
+            PlayerEvent::Error(message) => {
+                self.failed = true;
+                self.error_message = Some(format!("Playback failed: {}", message));
+            }
+        }
+        cx.notify();
+    }
+
+    /// Runs a player command, logging rather than surfacing failures: they
+    /// only happen while nothing is loaded.
+    fn with_player(&self, f: impl FnOnce(&Player) -> anyhow::Result<()>) {
+        if let Some(player) = &self.player
+            && let Err(e) = f(player)
+        {
+            tracing::debug!("Player command failed: {:#}", e);
+        }
+    }
+
+    fn toggle_paused(&mut self, cx: &mut Context<Self>) {
+        let paused = !self.paused;
+        self.with_player(|player| player.set_paused(paused));
+        self.show_controls(cx);
+    }
+
+    fn seek_relative(&mut self, seconds: f64, cx: &mut Context<Self>) {
+        self.with_player(|player| player.seek_relative(seconds));
+        self.report_next_position = true;
+        self.show_controls(cx);
+    }
+
+    fn seek_to_fraction(&mut self, fraction: f32, cx: &mut Context<Self>) {
+        if self.duration <= 0. {
+            return;
+        }
+        let position = self.duration * fraction as f64;
+        self.with_player(|player| player.seek(position));
+        // Shown right away rather than when the player catches up.
+        self.position = position;
+        self.report_next_position = true;
+        self.show_controls(cx);
+    }
+
+    fn set_volume(&mut self, volume: f64, cx: &mut Context<Self>) {
+        let volume = volume.clamp(0., 100.);
+        self.with_player(|player| {
+            player.set_volume(volume)?;
+            player.set_muted(false)
+        });
+        self.volume = volume;
+        self.show_controls(cx);
+    }
+
+    fn toggle_muted(&mut self, cx: &mut Context<Self>) {
+        let muted = !self.muted;
+        self.with_player(|player| player.set_muted(muted));
+        self.show_controls(cx);
+    }
+
+    /// Shows the controls and hides them again once the mouse rests, unless
+    /// playback is paused or a menu is open.
+    fn show_controls(&mut self, cx: &mut Context<Self>) {
+        if !self.controls_visible {
+            self.controls_visible = true;
+            cx.notify();
+        }
+        self.hide_controls_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(CONTROLS_TIMEOUT).await;
+            let _ = this.update(cx, |view, cx| {
+                if !view.paused && view.open_menu.is_none() && view.error_message.is_none() {
+                    view.controls_visible = false;
+                    cx.notify();
+                }
+            });
+        }));
+    }
+
+    /// Toggles pause after a click on the video, unless a second click makes
+    /// it a double-click first.
+    fn click_video(&mut self, cx: &mut Context<Self>) {
+        self.pending_click_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(DOUBLE_CLICK_WAIT).await;
+            let _ = this.update(cx, |view, cx| view.toggle_paused(cx));
+        }));
+    }
+
+    fn handle_key(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        if keystroke.modifiers.modified() {
+            return;
+        }
+        match keystroke.key.as_str() {
+            "space" | "k" => self.toggle_paused(cx),
+            "left" => self.seek_relative(-SEEK_STEP, cx),
+            "right" => self.seek_relative(SEEK_STEP, cx),
+            "up" => self.set_volume(self.volume + VOLUME_STEP, cx),
+            "down" => self.set_volume(self.volume - VOLUME_STEP, cx),
+            "m" => self.toggle_muted(cx),
+            "a" => self.cycle_audio(cx),
+            "c" => self.cycle_subtitles(cx),
+            // The keys mpv uses for subtitle delay.
+            "z" => self.adjust_subtitle_offset(-SUBTITLE_OFFSET_STEP, cx),
+            "x" => self.adjust_subtitle_offset(SUBTITLE_OFFSET_STEP, cx),
+            "f" => window.toggle_fullscreen(),
+            "escape" if self.open_menu.is_some() => {
+                self.open_menu = None;
+                cx.notify();
+            }
+            "escape" if window.is_fullscreen() => window.toggle_fullscreen(),
+            "escape" => (self.on_back)(window, cx),
+            _ => return,
+        }
+        cx.stop_propagation();
+    }
+
+    /// A horizontal bar filled to `value` that calls `on_change` with the
+    /// fraction clicked or dragged to.
+    fn render_slider(
+        &self,
+        id: &'static str,
+        value: f32,
+        bounds: &Rc<Cell<Bounds<Pixels>>>,
+        on_change: fn(&mut Self, f32, &mut Context<Self>),
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let theme = cx.theme();
+        let fraction = |position: Point<Pixels>, bounds: Bounds<Pixels>| {
+            if bounds.size.width <= px(0.) {
+                return None;
+            }
+            Some(((position.x - bounds.left()) / bounds.size.width).clamp(0., 1.))
+        };
+
+        div()
+            .id(id)
+            .relative()
+            .h_4()
+            .flex()
+            .items_center()
+            .cursor_pointer()
+            .child(
+                canvas(
+                    {
+                        let bounds = bounds.clone();
+                        move |painted, _, _| bounds.set(painted)
+                    },
+                    |_, _, _, _| {},
+                )
+                .absolute()
+                .size_full()
+            )
+            .child(
+                div()
+                    .w_full()
+                    .h_1()
+                    .rounded_full()
+                    .bg(white().opacity(0.3))
+                    .child(div().h_full().rounded_full().bg(theme.primary).w(relative(value.clamp(0., 1.))))
+            )
+            .on_mouse_down(MouseButton::Left, {
+                let bounds = bounds.clone();
+                cx.listener(move |this, event: &MouseDownEvent, _window, cx| {
+                    if let Some(fraction) = fraction(event.position, bounds.get()) {
+                        on_change(this, fraction, cx);
+                    }
+                })
+            })
+            .on_mouse_move({
+                let bounds = bounds.clone();
+                cx.listener(move |this, event: &MouseMoveEvent, _window, cx| {
+                    if event.pressed_button == Some(MouseButton::Left)
+                        && let Some(fraction) = fraction(event.position, bounds.get())
+                    {
+                        on_change(this, fraction, cx);
+                    }
+                })
+            })
+    }
+
+    /// The cues showing now, bottom-centered and clear of the controls.
+    fn render_subtitles(&self) -> Option<impl IntoElement> {
+        let subtitles = self.subtitles.as_ref()?;
+        let lines: Vec<String> = subtitles
+            .at(self.position - self.subtitle_offset)
+            .map(|cue| cue.text.clone())
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+
+        let style = &self.subtitle_style;
+        Some(
+            v_flex()
+                .absolute()
+                .left_0()
+                .w_full()
+                .bottom(if self.controls_visible { px(120.) } else { px(48.) })
+                .items_center()
+                .gap_1()
+                .children(lines.into_iter().map(|text| {
+                    div()
+                        .max_w(relative(0.8))
+                        .px_2()
+                        .rounded_sm()
+                        .bg(black().opacity(0.6))
+                        .text_center()
+                        .text_size(px(style.size))
+                        .text_color(self.subtitle_color)
+                        .when_some(style.font.clone(), |this, font| this.font_family(font))
+                        .child(text)
+                })),
+        )
+    }
+
+    /// A menu panel above the controls' right end.
+    fn render_menu(id: &'static str) -> Stateful<Div> {
+        v_flex()
+            .id(id)
+            .absolute()
+            .right_6()
+            .bottom(px(96.))
+            .w_80()
+            .max_h(px(360.))
+            .overflow_y_scroll()
+            .p_2()
+            .gap_1()
+            .rounded_md()
+            .bg(black().opacity(0.85))
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+    }
+
+    fn render_menu_item(id: impl Into<ElementId>, label: String, selected: bool) -> Button {
+        let button = Button::new(id).label(label).w_full();
+        if selected { button.primary() } else { button.ghost() }
+    }
+
+    fn render_audio_menu(&self, cx: &Context<Self>) -> impl IntoElement {
+        Self::render_menu("audio_menu").children(self.audio_tracks.iter().enumerate().map(|(i, track)| {
+            let index = track.index;
+            Self::render_menu_item(("audio", i), track.label.clone(), self.audio_stream_index == Some(index))
+                .on_click(cx.listener(move |this, _, _window, cx| this.select_audio(index, cx)))
+        }))
+    }
+
+    /// Picks a subtitle track or turns them off, and nudges their timing.
+    fn render_subtitle_menu(&self, cx: &Context<Self>) -> impl IntoElement {
+        let item = |id: ElementId, label: String, index: Option<i32>| {
+            Self::render_menu_item(id, label, self.subtitle_stream_index == index)
+                .on_click(cx.listener(move |this, _, _window, cx| this.select_subtitle(index, cx)))
+        };
+
+        Self::render_menu("subtitle_menu")
+            .child(item("subtitles_off".into(), "Off".to_string(), None))
+            .children(self.subtitle_tracks.iter().enumerate().map(|(i, track)| {
+                // Burning in takes a transcode, which is worth knowing up front.
+                let label = if track.is_text {
+                    track.label.clone()
+                } else {
+                    format!("{} (burned in)", track.label)
+                };
+                item(("subtitle", i).into(), label, Some(track.index))
+            }))
+            .child(
+                h_flex()
+                    .pt_1()
+                    .gap_2()
+                    .items_center()
+                    .child(div().flex_1().text_sm().text_color(white()).child("Offset"))
+                    .child(
+                        Button::new("subtitle_earlier")
+                            .ghost()
+                            .small()
+                            .label("−")
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.adjust_subtitle_offset(-SUBTITLE_OFFSET_STEP, cx)
+                            }))
+                    )
+                    .child(div().text_sm().text_color(white()).child(format_offset(self.subtitle_offset)))
+                    .child(
+                        Button::new("subtitle_later")
+                            .ghost()
+                            .small()
+                            .label("+")
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.adjust_subtitle_offset(SUBTITLE_OFFSET_STEP, cx)
+                            }))
+                    )
+            )
+    }
+
+    /// Opens `menu`, or closes it when it's the one open.
+    fn render_menu_button(
+        id: &'static str,
+        label: &'static str,
+        menu: TrackMenu,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        Button::new(id)
+            .ghost()
+            .label(label)
+            .on_click(cx.listener(move |this, _, _window, cx| {
+                this.open_menu = if this.open_menu == Some(menu) { None } else { Some(menu) };
+                this.show_controls(cx);
+                cx.notify();
+            }))
+    }
+
+    fn render_controls(&self, window: &Window, cx: &Context<Self>) -> impl IntoElement {
+        let progress = if self.duration > 0. { (self.position / self.duration) as f32 } else { 0. };
+        let volume = if self.muted { 0. } else { (self.volume / 100.) as f32 };
+
+        v_flex()
+            .absolute()
+            .bottom_0()
+            .left_0()
+            .w_full()
+            .px_6()
+            .pt_12()
+            .pb_4()
+            .gap_2()
+            .bg(linear_gradient(
+                0.,
+                linear_color_stop(black().opacity(0.8), 0.),
+                linear_color_stop(black().opacity(0.), 1.),
+            ))
+            // Clicks on the controls shouldn't also reach the video below.
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+            .child(self.render_slider(
+                "seek_bar",
+                progress,
+                &self.seek_bar_bounds,
+                Self::seek_to_fraction,
+                cx,
+            ))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        Button::new("play_pause")
+                            .ghost()
+                            .label(if self.paused { "▶" } else { "⏸" })
+                            .on_click(cx.listener(|this, _, _window, cx| this.toggle_paused(cx)))
+                    )
+                    .child(
+                        Button::new("rewind")
+                            .ghost()
+                            .label(format!("−{}s", SEEK_STEP))
+                            .on_click(cx.listener(|this, _, _window, cx| this.seek_relative(-SEEK_STEP, cx)))
+                    )
+                    .child(
+                        Button::new("forward")
+                            .ghost()
+                            .label(format!("+{}s", SEEK_STEP))
+                            .on_click(cx.listener(|this, _, _window, cx| this.seek_relative(SEEK_STEP, cx)))
+                    )
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(white())
+                            .child(format!("{} / {}", format_time(self.position), format_time(self.duration)))
+                    )
+                    .child(div().flex_1())
+                    .when(self.audio_tracks.len() > 1, |this| {
+                        this.child(Self::render_menu_button("audio", "Audio", TrackMenu::Audio, cx))
+                    })
+                    .when(!self.subtitle_tracks.is_empty(), |this| {
+                        this.child(Self::render_menu_button("subtitles", "Subtitles", TrackMenu::Subtitles, cx))
+                    })
+                    .child(
+                        Button::new("mute")
+                            .ghost()
+                            .label(if self.muted || self.volume <= 0. { "🔇" } else { "🔊" })
+                            .on_click(cx.listener(|this, _, _window, cx| this.toggle_muted(cx)))
+                    )
+                    .child(div().w_24().child(self.render_slider(
+                        "volume_bar",
+                        volume,
+                        &self.volume_bar_bounds,
+                        |this, fraction, cx| this.set_volume(fraction as f64 * 100., cx),
+                        cx,
+                    )))
+                    .child(
+                        Button::new("fullscreen")
+                            .ghost()
+                            .label(if window.is_fullscreen() { "Exit Fullscreen" } else { "Fullscreen" })
+                            .on_click(cx.listener(|_, _, window, _cx| window.toggle_fullscreen()))
+                    )
+            )
+    }
+}
+
+/// Asks for `stream`'s version of the item again with the given audio and
+/// subtitle streams.
+fn stream_request(
+    user_id: Option<String>,
+    stream: &StreamChoice,
+    audio_stream_index: Option<i32>,
+    subtitle_stream_index: Option<i32>,
+) -> PlaybackRequest {
+    PlaybackRequest {
+        user_id,
+        media_source_id: stream.media_source.id.clone(),
+        audio_stream_index,
+        // Unset would get the default track, which may be burned in.
+        subtitle_stream_index: Some(subtitle_stream_index.unwrap_or(-1)),
+    }
+}
+
+/// The subtitle offset as "+0.3s" or "−1.2s".
+fn format_offset(seconds: f64) -> String {
+    if seconds < 0. {
+        format!("−{:.1}s", -seconds)
+    } else {
+        format!("+{:.1}s", seconds)
+    }
+}
+
+/// A playback time as "42:10" or "1:02:03".
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.) as u64;
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+/// "Show · S1:E2 · Episode" for episodes, otherwise the item's name.
+fn title_of(item: &BaseItemDto) -> String {
+    let name = item.name.clone().unwrap_or_default();
+    match (item.type_, &item.series_name, item.parent_index_number, item.index_number) {
+        (Some(BaseItemKind::Episode), Some(series), Some(season), Some(episode)) => {
+            format!("{} · S{}:E{} · {}", series, season, episode, name)
+        }
+        _ => name,
+    }
+}
+
+/// How the stream reaches us, for the title bar.
+fn play_method_label(method: PlayMethod) -> &'static str {
+    match method {
+        PlayMethod::DirectPlay => "Direct Play",
+        PlayMethod::DirectStream => "Direct Stream",
+        _ => "Transcoding",
+    }
+}
+
+fn describe_error(error: &PlaybackError) -> String {
+    match error {
+        PlaybackError::Api(ApiError::Connect(_) | ApiError::Timeout(_)) => {
+            "Could not reach the server. Check your connection and try again.".to_string()
+        }
+        PlaybackError::Api(ApiError::NotFound) => "This item no longer exists on the server.".to_string(),
+        PlaybackError::Api(other) => format!("Failed to load this item: {}", other),
+        other => other.to_string(),
+    }
+}
+
+impl Render for PlayerView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // Frames are rendered at the size they're shown at, in device pixels.
+        if let Some(player) = &self.player {
+            let size = window.viewport_size();
+            let scale = window.scale_factor();
+            player.set_render_size(
+                (f32::from(size.width) * scale) as u32,
+                (f32::from(size.height) * scale) as u32,
+            );
+        }
+
+        let theme = cx.theme();
+        let title = self.item.as_ref().map(title_of).unwrap_or_default();
+        let method = self.stream.as_ref().map(|stream| play_method_label(stream.play_method()));
+        let status = if self.is_loading || (self.frame.is_none() && self.error_message.is_none()) {
+            Some("Loading...")
+        } else if self.buffering {
+            Some("Buffering...")
+        } else {
+            None
+        };
+
+        div()
+            .id("player")
+            .key_context("PlayerView")
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(Self::handle_key))
+            .on_mouse_move(cx.listener(|this, _: &MouseMoveEvent, _window, cx| this.show_controls(cx)))
+            .relative()
+            .size_full()
+            .bg(black())
+            .when(!self.controls_visible, |this| this.cursor(CursorStyle::None))
+            .child(
+                div()
+                    .size_full()
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, event: &MouseDownEvent, window, cx| {
+                            match event.click_count {
+                                1 => this.click_video(cx),
+                                2 => {
+                                    this.pending_click_task = None;
+                                    window.toggle_fullscreen();
+                                }
+                                _ => {}
+                            }
+                        }),
+                    )
+                    .when_some(self.frame.clone(), |this, frame| {
+                        this.child(img(frame).size_full().object_fit(ObjectFit::Contain))
+                    })
+            )
+            .children(self.render_subtitles())
+            .when_some(status, |this, status| {
+                this.child(
+                    div()
+                        .absolute()
+                        .size_full()
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .text_color(white())
+                        .child(status)
+                )
+            })
+            .when_some(self.error_message.clone(), |this, message| {
+                this.child(
+                    v_flex()
+                        .absolute()
+                        .size_full()
+                        .items_center()
+                        .justify_center()
+                        .gap_3()
+                        .child(div().text_sm().text_color(theme.danger).child(message))
+                        .when(self.player.is_some(), |this| {
+                            this.child(
+                                Button::new("retry")
+                                    .label("Retry")
+                                    .on_click(cx.listener(|this, _, _window, cx| this.retry(cx)))
+                            )
+                        })
+                )
+            })
+            .when(self.controls_visible, |this| {
+                this.child(
+                    h_flex()
+                        .absolute()
+                        .top_0()
+                        .left_0()
+                        .w_full()
+                        .p_4()
+                        .gap_3()
+                        .items_center()
+                        .bg(linear_gradient(
+                            180.,
+                            linear_color_stop(black().opacity(0.8), 0.),
+                            linear_color_stop(black().opacity(0.), 1.),
+                        ))
+                        .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+                        .child(
+                            Button::new("back")
+                                .ghost()
+                                .icon(IconName::ArrowLeft)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    (this.on_back)(window, cx);
+                                }))
+                        )
+                        .child(div().flex_1().text_lg().font_bold().text_color(white()).truncate().child(title))
+                        .when_some(method, |this, method| {
+                            this.child(div().text_sm().text_color(white().opacity(0.7)).child(method))
+                        })
+                )
+                .child(self.render_controls(window, cx))
+                .map(|this| match self.open_menu {
+                    Some(TrackMenu::Audio) => this.child(self.render_audio_menu(cx)),
+                    Some(TrackMenu::Subtitles) => this.child(self.render_subtitle_menu(cx)),
+                    None => this,
+                })
+            })
+    }
+}
@@ -0,0 +1,455 @@
+use crate::api::{
+    ApiError, BaseItemDto, BaseItemKind, GetItemsQuery, GetSearchHintsQuery, ImageType, ItemFields, JellyfinClient,
+    SearchHint,
+};
+use crate::images::{Artwork, ImageService};
+use crate::state::Screen;
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::{button::{Button, ButtonVariants}, input::{Input, InputEvent, InputState}, *};
+use std::time::Duration;
+
+/// How long typing has to pause before a search goes out.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Results asked of each endpoint. More than this is a sign to type more.
+const RESULT_LIMIT: i32 = 60;
+
+const THUMBNAIL_SIZE: Size<Pixels> = Size { width: px(40.), height: px(60.) };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResultGroup {
+    Movies,
+    Series,
+    Episodes,
+    People,
+    Artists,
+    Albums,
+    Songs,
+}
+
+impl ResultGroup {
+    /// Display order of the groups.
+    const ALL: [Self; 7] =
+        [Self::Movies, Self::Series, Self::Episodes, Self::People, Self::Artists, Self::Albums, Self::Songs];
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Movies => "Movies",
+            Self::Series => "Series",
+            Self::Episodes => "Episodes",
+            Self::People => "People",
+            Self::Artists => "Artists",
+            Self::Albums => "Albums",
+            Self::Songs => "Songs",
+        }
+    }
+
+    fn for_kind(kind: BaseItemKind) -> Option<Self> {
+        match kind {
+            BaseItemKind::Movie => Some(Self::Movies),
+            BaseItemKind::Series => Some(Self::Series),
+            BaseItemKind::Episode => Some(Self::Episodes),
+            BaseItemKind::Person => Some(Self::People),
+            BaseItemKind::MusicArtist => Some(Self::Artists),
+            BaseItemKind::MusicAlbum => Some(Self::Albums),
+            BaseItemKind::Audio => Some(Self::Songs),
+            _ => None,
+        }
+    }
+}
+
+/// One row of the results, from either endpoint.
+#[derive(Debug, Clone)]
+struct SearchResult {
+    id: String,
+    group: ResultGroup,
+    title: String,
+    subtitle: Option<String>,
+    artwork: Option<Artwork>,
+}
+
+impl SearchResult {
+    fn from_item(item: &BaseItemDto) -> Option<Self> {
+        let group = ResultGroup::for_kind(item.type_?)?;
+        let subtitle = match group {
+            ResultGroup::Episodes => {
+                let series = item.series_name.clone().unwrap_or_default();
+                match (item.parent_index_number, item.index_number) {
+                    (Some(season), Some(episode)) => Some(format!("{} · S{}:E{}", series, season, episode)),
+                    _ => Some(series),
+                }
+            }
+            ResultGroup::Albums => item.album_artist.clone(),
+            ResultGroup::Songs => item.artists.as_ref().map(|artists| artists.join(", ")),
+            _ => item.production_year.map(|year| year.to_string()),
+        };
+        Some(Self {
+            id: item.id.clone()?,
+            group,
+            title: item.name.clone().unwrap_or_default(),
+            subtitle: subtitle.filter(|s| !s.is_empty()),
+            artwork: Artwork::portrait(item),
+        })
+    }
+
+    fn from_hint(hint: &SearchHint) -> Option<Self> {
+        let group = ResultGroup::for_kind(hint.type_?)?;
+        let id = hint.item_id.clone().or_else(|| hint.id.clone())?;
+        let subtitle = match group {
+            ResultGroup::Episodes => hint.series.clone(),
+            ResultGroup::Albums => hint.album_artist.clone(),
+            ResultGroup::Songs => hint.artists.as_ref().map(|artists| artists.join(", ")),
+            ResultGroup::People | ResultGroup::Artists => None,
+            _ => hint.production_year.map(|year| year.to_string()),
+        };
+        let artwork = hint.primary_image_tag.clone().map(|tag| Artwork {
+            item_id: id.clone(),
+            image_type: ImageType::Primary,
+            tag: Some(tag),
+            blur_hash: None,
+        });
+        Some(Self {
+            id,
+            group,
+            title: hint.name.clone().unwrap_or_default(),
+            subtitle: subtitle.filter(|s| !s.is_empty()),
+            artwork,
+        })
+    }
+}
+
+/// Finds movies, shows, people and music as the user types.
+pub struct SearchView {
+    client: JellyfinClient,
+    images: ImageService,
+    user_id: Option<String>,
+    input: Entity<InputState>,
+    query: String,
+    /// Results in display order: grouped, and in server order within a group.
+    results: Vec<SearchResult>,
+    selected: Option<usize>,
+    is_searching: bool,
+    error_message: Option<String>,
+    /// The pending or running search. Replacing it cancels the old one, which
+    /// is what debounces typing.
+    search_task: Option<Task<()>>,
+    scroll_handle: ScrollHandle,
+    on_navigate: Box<dyn Fn(Screen, &mut Window, &mut Context<SearchView>) + 'static>,
+    on_back: Box<dyn Fn(&mut Window, &mut Context<SearchView>) + 'static>,
+    _subscription: Subscription,
+}
+
+impl SearchView {
+    pub fn new(
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        client: JellyfinClient,
+        images: ImageService,
+        user_id: Option<String>,
+        on_navigate: impl Fn(Screen, &mut Window, &mut Context<SearchView>) + 'static,
+        on_back: impl Fn(&mut Window, &mut Context<SearchView>) + 'static,
+    ) -> Self {
+        let input = cx.new(|cx| InputState::new(window, cx).placeholder("Search movies, shows, people and music"));
+        let subscription = cx.subscribe_in(&input, window, |this, _, event: &InputEvent, _window, cx| {
+            if let InputEvent::Change = event {
+                this.query_changed(cx);
+            }
+        });
+        window.focus(&input.focus_handle(cx));
+
+        Self {
+            client,
+            images,
+            user_id,
+            input,
+            query: String::new(),
+            results: Vec::new(),
+            selected: None,
+            is_searching: false,
+            error_message: None,
+            search_task: None,
+            scroll_handle: ScrollHandle::new(),
+            on_navigate: Box::new(on_navigate),
+            on_back: Box::new(on_back),
+            _subscription: subscription,
+        }
+    }
+
+    fn query_changed(&mut self, cx: &mut Context<Self>) {
+        let query = self.input.read(cx).value().trim().to_string();
+        if query == self.query {
+            return;
+        }
+        self.query = query.clone();
+        self.error_message = None;
+
+        if query.is_empty() {
+            self.search_task = None;
+            self.is_searching = false;
+            self.results.clear();
+            self.selected = None;
+            cx.notify();
+            return;
+        }
+
+        self.is_searching = true;
+        let client = self.client.clone();
+        let user_id = self.user_id.clone();
+        self.search_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(DEBOUNCE).await;
+            let result = search(&client, user_id, &query).await;
+            let _ = this.update(cx, |view, cx| {
+                view.is_searching = false;
+                match result {
+                    Ok(results) => {
+                        view.selected = (!results.is_empty()).then_some(0);
+                        view.results = results;
+                        view.scroll_handle.set_offset(point(px(0.), px(0.)));
+                    }
+                    Err(e) => {
+                        view.results.clear();
+                        view.selected = None;
+                        view.error_message = Some(describe_error(&e));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+        cx.notify();
+    }
+
+    fn move_selection(&mut self, delta: isize, cx: &mut Context<Self>) {
+        if self.results.is_empty() {
+            return;
+        }
+        let last = self.results.len() - 1;
+        let selected = match self.selected {
+            Some(selected) => selected.saturating_add_signed(delta).min(last),
+            None if delta < 0 => last,
+            None => 0,
+        };
+        self.selected = Some(selected);
+        self.scroll_handle.scroll_to_item(self.child_index(selected));
+        cx.notify();
+    }
+
+    fn open(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(result) = self.results.get(index) {
+            (self.on_navigate)(Screen::Item(result.id.clone()), window, cx);
+        }
+    }
+
+    /// Up, down and enter drive the results while typing stays in the input.
+    fn handle_key(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        if keystroke.modifiers.modified() {
+            return;
+        }
+        match keystroke.key.as_str() {
+            "down" => self.move_selection(1, cx),
+            "up" => self.move_selection(-1, cx),
+            "enter" => {
+                if let Some(selected) = self.selected {
+                    self.open(selected, window, cx);
+                }
+            }
+            "escape" if !self.query.is_empty() => {
+                self.input.update(cx, |state, cx| state.set_value("", window, cx));
+            }
+            _ => return,
+        }
+        cx.stop_propagation();
+    }
+
+    /// Position of a result among the list's children, which also hold one
+    /// header per group. The status line only shows without results.
+    fn child_index(&self, index: usize) -> usize {
+        let headers = ResultGroup::ALL
+            .iter()
+            .filter(|group| self.results[..=index].iter().any(|result| result.group == **group))
+            .count();
+        index + headers
+    }
+
+    fn render_result(&self, index: usize, result: &SearchResult, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let selected = self.selected == Some(index);
+        let round = matches!(result.group, ResultGroup::People | ResultGroup::Artists);
+        let image = self.images.image(result.artwork.as_ref(), THUMBNAIL_SIZE.width, cx);
+
+        h_flex()
+            .id(("search_result", index))
+            .px_4()
+            .py_1()
+            .gap_3()
+            .items_center()
+            .cursor_pointer()
+            .when(selected, |this| this.bg(theme.accent))
+            .hover(|style| style.bg(theme.accent))
+            .child(
+                div()
+                    .w(THUMBNAIL_SIZE.width)
+                    .h(if round { THUMBNAIL_SIZE.width } else { THUMBNAIL_SIZE.height })
+                    .flex_none()
+                    .overflow_hidden()
+                    .bg(theme.muted)
+                    .map(|this| if round { this.rounded_full() } else { this.rounded_sm() })
+                    .when_some(image, |this, image| {
+                        this.child(img(image).size_full().object_fit(ObjectFit::Cover))
+                    })
+            )
+            .child(
+                v_flex()
+                    .flex_1()
+                    .overflow_hidden()
+                    .child(div().truncate().child(result.title.clone()))
+                    .when_some(result.subtitle.clone(), |this, subtitle| {
+                        this.child(div().text_sm().text_color(theme.muted_foreground).truncate().child(subtitle))
+                    })
+            )
+            .on_click(cx.listener(move |this, _, window, cx| this.open(index, window, cx)))
+    }
+}
+
+/// Runs both searches at once. `/Items` brings full items, with the image
+/// tags and blur hashes the cards use, but it doesn't cover people or
+/// artists; those come from `/Search/Hints`, which also stands in for
+/// everything else when `/Items` fails.
+async fn search(client: &JellyfinClient, user_id: Option<String>, term: &str) -> Result<Vec<SearchResult>, ApiError> {
+    let hints_query = GetSearchHintsQuery {
+        search_term: term.to_string(),
+        user_id: user_id.clone(),
+        limit: Some(RESULT_LIMIT),
+        include_people: Some(true),
+        include_artists: Some(true),
+        include_media: Some(true),
+        include_genres: Some(false),
+        include_studios: Some(false),
+        ..Default::default()
+    };
+    let items_query = GetItemsQuery {
+        search_term: Some(term.to_string()),
+        user_id,
+        recursive: Some(true),
+        limit: Some(RESULT_LIMIT),
+        include_item_types: Some(vec![
+            BaseItemKind::Movie,
+            BaseItemKind::Series,
+            BaseItemKind::Episode,
+            BaseItemKind::MusicAlbum,
+            BaseItemKind::Audio,
+        ]),
+        fields: Some(vec![ItemFields::PrimaryImageAspectRatio]),
+        enable_user_data: Some(true),
+        ..Default::default()
+    };
+
+    let (hints, items) = tokio::join!(client.get_search_hints(&hints_query), client.get_items(&items_query));
+
+    let mut results: Vec<SearchResult> = Vec::new();
+    match items {
+        Ok(items) => results.extend(items.items.iter().flatten().filter_map(SearchResult::from_item)),
+        Err(e) => tracing::warn!("Item search failed: {}", e),
+    }
+    let items_found = !results.is_empty();
+
+    match hints {
+        Ok(hints) => {
+            let hints = hints.search_hints.unwrap_or_default();
+            results.extend(
+                hints
+                    .iter()
+                    .filter_map(SearchResult::from_hint)
+                    .filter(|hint| !items_found || matches!(hint.group, ResultGroup::People | ResultGroup::Artists)),
+            );
+        }
+        // Without either endpoint there's nothing to show.
+        Err(e) if !items_found => return Err(e),
+        Err(e) => tracing::warn!("Search hints failed: {}", e),
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    results.retain(|result| seen.insert(result.id.clone()));
+    // Stable, so the server's relevance order survives within each group.
+    results.sort_by_key(|result| ResultGroup::ALL.iter().position(|group| *group == result.group));
+    Ok(results)
+}
+
+fn describe_error(error: &ApiError) -> String {
+    match error {
+        ApiError::Connect(_) | ApiError::Timeout(_) => {
+            "Could not reach the server. Check your connection and try again.".to_string()
+        }
+        other => format!("Search failed: {}", other),
+    }
+}
+
+impl Render for SearchView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let mut rows = Vec::new();
+        let mut group = None;
+        for (index, result) in self.results.iter().enumerate() {
+            if group != Some(result.group) {
+                group = Some(result.group);
+                rows.push(
+                    div()
+                        .px_4()
+                        .pt_4()
+                        .pb_1()
+                        .text_sm()
+                        .font_bold()
+                        .text_color(theme.muted_foreground)
+                        .child(result.group.title())
+                        .into_any_element(),
+                );
+            }
+            rows.push(self.render_result(index, result, cx).into_any_element());
+        }
+
+        let status = if let Some(message) = self.error_message.clone() {
+            Some(div().text_color(theme.danger).child(message))
+        } else if self.is_searching && self.results.is_empty() {
+            Some(div().text_color(theme.muted_foreground).child("Searching..."))
+        } else if !self.query.is_empty() && self.results.is_empty() {
+            Some(div().text_color(theme.muted_foreground).child(format!("Nothing found for \"{}\".", self.query)))
+        } else {
+            None
+        };
+
+        v_flex()
+            .size_full()
+            .key_context("SearchView")
+            .capture_key_down(cx.listener(Self::handle_key))
+            .bg(theme.background)
+            .child(
+                h_flex()
+                    .p_4()
+                    .gap_3()
+                    .items_center()
+                    .border_b_1()
+                    .border_color(theme.border)
+                    .child(
+                        Button::new("back")
+                            .ghost()
+                            .icon(IconName::ArrowLeft)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                (this.on_back)(window, cx);
+                            }))
+                    )
+                    .child(div().flex_1().child(Input::new(&self.input)))
+            )
+            .child(
+                v_flex()
+                    .id("search_results")
+                    .flex_1()
+                    .pb_4()
+                    .overflow_y_scroll()
+                    .track_scroll(&self.scroll_handle)
+                    .children(status.map(|status| div().p_4().text_sm().child(status)))
+                    .children(rows)
+            )
+    }
+}
@@ -0,0 +1,239 @@
+use crate::config::SubtitleStyle;
+use anyhow::Result;
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::{button::{Button, ButtonVariants}, input::{Input, InputEvent, InputState}, *};
+
+/// Subtitle sizes outside this range are unreadable or cover the picture.
+const MIN_SUBTITLE_SIZE: f32 = 8.;
+const MAX_SUBTITLE_SIZE: f32 = 128.;
+
+/// App-wide preferences. For now that's how the player draws subtitles,
+/// which applies from the next playback on.
+pub struct SettingsView {
+    font_input: Entity<InputState>,
+    size_input: Entity<InputState>,
+    color_input: Entity<InputState>,
+    offset_input: Entity<InputState>,
+    on_save: Box<dyn Fn(SubtitleStyle, &mut Window, &mut Context<SettingsView>) -> Result<()> + 'static>,
+    on_back: Box<dyn Fn(&mut Window, &mut Context<SettingsView>) + 'static>,
+    error_message: Option<String>,
+    /// Set after a save until the next edit.
+    saved: bool,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl SettingsView {
+    pub fn new(
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        subtitle_style: SubtitleStyle,
+        on_save: impl Fn(SubtitleStyle, &mut Window, &mut Context<SettingsView>) -> Result<()> + 'static,
+        on_back: impl Fn(&mut Window, &mut Context<SettingsView>) + 'static,
+    ) -> Self {
+        let font_input = cx.new(|cx| InputState::new(window, cx).placeholder("Interface font"));
+        let size_input = cx.new(|cx| InputState::new(window, cx));
+        let color_input = cx.new(|cx| InputState::new(window, cx).placeholder("#ffffff"));
+        let offset_input = cx.new(|cx| InputState::new(window, cx).placeholder("0"));
+
+        // Edits show in the preview straight away and clear the last outcome.
+        let _subscriptions = [&font_input, &size_input, &color_input, &offset_input]
+            .into_iter()
+            .map(|input| {
+                cx.subscribe_in(input, window, |this, _, event: &InputEvent, _window, cx| {
+                    if let InputEvent::Change = event {
+                        this.error_message = None;
+                        this.saved = false;
+                        cx.notify();
+                    }
+                })
+            })
+            .collect();
+
+        let mut this = Self {
+            font_input,
+            size_input,
+            color_input,
+            offset_input,
+            on_save: Box::new(on_save),
+            on_back: Box::new(on_back),
+            error_message: None,
+            saved: false,
+            _subscriptions,
+        };
+        this.show_style(&subtitle_style, window, cx);
+        this
+    }
+
+    /// Fills the fields in from `style`.
+    fn show_style(&mut self, style: &SubtitleStyle, window: &mut Window, cx: &mut Context<Self>) {
+        let values = [
+            (&self.font_input, style.font.clone().unwrap_or_default()),
+            (&self.size_input, style.size.to_string()),
+            (&self.color_input, style.color.clone()),
+            (&self.offset_input, style.offset.to_string()),
+        ];
+        for (input, value) in values {
+            input.update(cx, |state, cx| state.set_value(value, window, cx));
+        }
+        cx.notify();
+    }
+
+    /// The style the fields describe, or what's wrong with them.
+    fn read_style(&self, cx: &App) -> Result<SubtitleStyle, String> {
+        let font = self.font_input.read(cx).value().trim().to_string();
+
+        let size = self
+            .size_input
+            .read(cx)
+            .value()
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|size| (MIN_SUBTITLE_SIZE..=MAX_SUBTITLE_SIZE).contains(size))
+            .ok_or_else(|| format!("Size must be a number from {} to {}.", MIN_SUBTITLE_SIZE, MAX_SUBTITLE_SIZE))?;
+
+        let color = self.color_input.read(cx).value().trim().to_string();
+        if Rgba::try_from(color.as_str()).is_err() {
+            return Err("Color must look like #ffffff, or #ffffffcc with transparency.".to_string());
+        }
+
+        let offset = self
+            .offset_input
+            .read(cx)
+            .value()
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|offset| offset.is_finite())
+            .ok_or_else(|| "Delay must be a number of seconds, e.g. 0.5 or -1.".to_string())?;
+
+        Ok(SubtitleStyle {
+            font: (!font.is_empty()).then_some(font),
+            size,
+            color,
+            offset,
+        })
+    }
+
+    fn save(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        match self.read_style(cx) {
+            Ok(style) => match (self.on_save)(style, window, cx) {
+                Ok(()) => self.saved = true,
+                Err(e) => {
+                    tracing::error!("Failed to save settings: {:#}", e);
+                    self.error_message = Some(format!("Failed to save settings: {:#}", e));
+                }
+            },
+            Err(message) => self.error_message = Some(message),
+        }
+        cx.notify();
+    }
+
+    fn render_field(label: &'static str, hint: &'static str, input: &Entity<InputState>, cx: &App) -> impl IntoElement {
+        v_flex()
+            .gap_1()
+            .child(div().text_sm().font_bold().child(label))
+            .child(Input::new(input))
+            .child(div().text_xs().text_color(cx.theme().muted_foreground).child(hint))
+    }
+
+    /// Sample text drawn the way the player draws subtitles.
+    fn render_preview(style: &SubtitleStyle) -> impl IntoElement {
+        let color = Rgba::try_from(style.color.as_str()).map_or(white(), Hsla::from);
+        div()
+            .h(px(MAX_SUBTITLE_SIZE + 32.))
+            .rounded_md()
+            .bg(black())
+            .flex()
+            .items_center()
+            .justify_center()
+            .child(
+                div()
+                    .px_2()
+                    .rounded_sm()
+                    .bg(black().opacity(0.6))
+                    .text_center()
+                    .text_size(px(style.size))
+                    .text_color(color)
+                    .when_some(style.font.clone(), |this, font| this.font_family(font))
+                    .child("The quick brown fox"),
+            )
+    }
+}
+
+impl Render for SettingsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let style = self.read_style(cx);
+        let theme = cx.theme();
+
+        div()
+            .id("settings")
+            .size_full()
+            .overflow_y_scroll()
+            .flex()
+            .flex_col()
+            .items_center()
+            .bg(theme.background)
+            .child(
+                v_flex()
+                    .w_96()
+                    .py_8()
+                    .gap_4()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(
+                                Button::new("back")
+                                    .ghost()
+                                    .icon(IconName::ArrowLeft)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        (this.on_back)(window, cx);
+                                    }))
+                            )
+                            .child(div().text_xl().font_bold().child("Settings"))
+                    )
+                    .child(div().text_lg().font_bold().child("Subtitles"))
+                    .child(Self::render_field("Font", "Leave empty to use the interface font.", &self.font_input, cx))
+                    .child(Self::render_field("Size", "Text height in pixels.", &self.size_input, cx))
+                    .child(Self::render_field("Color", "As #rrggbb or #rrggbbaa.", &self.color_input, cx))
+                    .child(Self::render_field(
+                        "Delay",
+                        "Seconds to show subtitles later, or earlier when negative.",
+                        &self.offset_input,
+                        cx,
+                    ))
+                    .children(style.as_ref().ok().map(Self::render_preview))
+                    .children(self.error_message.as_ref().map(|msg| {
+                        div().text_sm().text_color(theme.danger).child(msg.clone())
+                    }))
+                    .when(self.saved, |this| {
+                        this.child(
+                            div()
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child("Saved. Changes apply from the next video you play.")
+                        )
+                    })
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .justify_end()
+                            .child(
+                                Button::new("reset")
+                                    .label("Reset to Defaults")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.show_style(&SubtitleStyle::default(), window, cx);
+                                    }))
+                            )
+                            .child(
+                                Button::new("save")
+                                    .primary()
+                                    .label("Save")
+                                    .on_click(cx.listener(|this, _, window, cx| this.save(window, cx)))
+                            )
+                    )
+            )
+    }
+}
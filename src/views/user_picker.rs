@@ -0,0 +1,157 @@
+use crate::api::JellyfinClient;
+use crate::components::{fetch_user_avatar, Avatar};
+use crate::config::ServerUser;
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::{button::{Button, ButtonVariants}, *};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Lists the users signed in on one server so the household can switch
+/// between them without typing passwords again.
+pub struct UserPickerView {
+    server_name: String,
+    users: Vec<ServerUser>,
+    avatars: HashMap<String, Arc<Image>>,
+    on_select: Box<dyn Fn(&ServerUser, &mut Window, &mut Context<UserPickerView>) + 'static>,
+    on_add_user: Box<dyn Fn(&mut Window, &mut Context<UserPickerView>) + 'static>,
+    on_back: Box<dyn Fn(&mut Window, &mut Context<UserPickerView>) + 'static>,
+}
+
+impl UserPickerView {
+    pub fn new(
+        cx: &mut Context<Self>,
+        client: JellyfinClient,
+        server_name: String,
+        users: Vec<ServerUser>,
+        on_select: impl Fn(&ServerUser, &mut Window, &mut Context<UserPickerView>) + 'static,
+        on_add_user: impl Fn(&mut Window, &mut Context<UserPickerView>) + 'static,
+        on_back: impl Fn(&mut Window, &mut Context<UserPickerView>) + 'static,
+    ) -> Self {
+        let this = Self {
+            server_name,
+            users,
+            avatars: HashMap::new(),
+            on_select: Box::new(on_select),
+            on_add_user: Box::new(on_add_user),
+            on_back: Box::new(on_back),
+        };
+        this.load_avatars(client, cx);
+        this
+    }
+
+    /// Fetches each user's primary image. Users without one keep their initial.
+    ///
+    /// Users carried over from a single-user config have no name until they
+    /// sign in again, which stores it; until then their token looks it up.
+    fn load_avatars(&self, client: JellyfinClient, cx: &mut Context<Self>) {
+        let users = self.users.clone();
+
+        cx.spawn(async move |this, cx| {
+            for user in users {
+                if user.name.is_empty()
+                    && let Some(token) = user.access_token.clone()
+                    && let Ok(found) = client.clone().with_access_token(token).get_user_by_id(&user.id).await
+                    && let Some(name) = found.name
+                {
+                    let updated = this.update(cx, |view, cx| {
+                        if let Some(shown) = view.users.iter_mut().find(|u| u.id == user.id) {
+                            shown.name = name;
+                        }
+                        cx.notify();
+                    });
+                    if updated.is_err() {
+                        return;
+                    }
+                }
+
+                let Some(image) = fetch_user_avatar(&client, &user.id, user.primary_image_tag.clone()).await else {
+                    continue;
+                };
+                let updated = this.update(cx, |view, cx| {
+                    view.avatars.insert(user.id, image);
+                    cx.notify();
+                });
+                if updated.is_err() {
+                    return;
+                }
+            }
+        })
+        .detach();
+    }
+}
+
+/// The name on a user's tile, for users whose name isn't known yet too.
+fn display_name(user: &ServerUser) -> SharedString {
+    if user.name.is_empty() {
+        "Unknown user".into()
+    } else {
+        user.name.clone().into()
+    }
+}
+
+impl Render for UserPickerView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        div()
+            .size_full()
+            .flex()
+            .flex_col()
+            .items_center()
+            .justify_center()
+            .bg(theme.background)
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .items_center()
+                    .gap_6()
+                    .child(div().text_xl().font_bold().child("Who's watching?"))
+                    .child(div().text_sm().text_color(theme.muted_foreground).child(self.server_name.clone()))
+                    .child(
+                        h_flex()
+                            .flex_wrap()
+                            .justify_center()
+                            .gap_4()
+                            .children(self.users.iter().enumerate().map(|(i, user)| {
+                                let user_select = user.clone();
+                                v_flex()
+                                    .id(("user", i))
+                                    .w_32()
+                                    .p_3()
+                                    .gap_2()
+                                    .items_center()
+                                    .rounded_md()
+                                    .cursor_pointer()
+                                    .hover(|s| s.bg(theme.list_hover))
+                                    .child(Avatar::new(display_name(user), self.avatars.get(&user.id).cloned()))
+                                    .child(div().text_sm().text_center().child(display_name(user)))
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        (this.on_select)(&user_select, window, cx);
+                                    }))
+                            }))
+                    )
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                Button::new("back")
+                                    .ghost()
+                                    .label("Back")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        (this.on_back)(window, cx);
+                                    }))
+                            )
+                            .child(
+                                Button::new("add_user")
+                                    .primary()
+                                    .label("Add User")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        (this.on_add_user)(window, cx);
+                                    }))
+                            )
+                    )
+            )
+    }
+}